@@ -49,9 +49,36 @@ pub mod io {
     //!
     //! This module provides I/O functions for usr_bin. Note that `kprintln` is privileged and is not exposed here.
     pub use crate::io::vga::prelude::{Colour, COL};
+    pub use crate::io::vga::{cursor, set_cursor, write_at};
     pub use crate::{eprintln, print, println, rprint, wprintln};
 }
 
+pub mod keys {
+    //! Keyboard input
+    //!
+    //! This module provides the raw key events for usr_bin, including keys without a character and releases.
+    pub use crate::keys::{
+        subscribe, unsubscribe, Key, KeyCode, KeyState, Modifiers, Subscription,
+    };
+}
+
+pub mod mouse {
+    //! Mouse input
+    //!
+    //! This module provides the mouse events and the pointer position for usr_bin.
+    pub use crate::io::mouse::{
+        position, subscribe, unsubscribe, Buttons, MouseEvent, Subscription,
+    };
+}
+
+pub mod log {
+    //! Kernel log
+    //!
+    //! This module provides the kernel log facade for usr_bin, including its levels and custom sinks.
+    pub use crate::log::{add_sink, clear, dmesg, set_level, Level, Record, Sink};
+    pub use crate::{debug, error, info, trace, warn};
+}
+
 pub mod time {
     //! Time related features and types
     //!
@@ -70,11 +97,64 @@ pub mod scripting {
     // Represents the result of a command execution.
     pub type CmdRes = Result<Option<String>, String>;
 
+    // Represents the result of a command taking its calling context, which can fail with an exit status.
+    pub type CtxRes = Result<Option<String>, CmdErr>;
+
+    // Represents the exit status of a command, 0 being a success.
+    pub type ExitCode = u8;
+
+    // Represents the failure of a command.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct CmdErr {
+        /// The exit status, never 0.
+        pub code: ExitCode,
+        /// The error message, nothing is printed if it is empty.
+        pub msg: String,
+    }
+
+    impl CmdErr {
+        /// Creates an error with an exit status and a message.
+        pub fn new(code: ExitCode, msg: impl Into<String>) -> Self {
+            Self {
+                code: code.max(1),
+                msg: msg.into(),
+            }
+        }
+
+        /// Creates an error with an exit status and without a message.
+        pub fn code(code: ExitCode) -> Self {
+            Self::new(code, String::new())
+        }
+    }
+
+    /// Errors of commands taking only arguments have the exit status 1.
+    impl From<String> for CmdErr {
+        fn from(msg: String) -> Self {
+            Self::new(1, msg)
+        }
+    }
+
     // Represents the command arguments.
     pub type CmdArgs<'a> = &'a [&'a str];
 
+    // Represents the calling context of a command.
+    pub struct CmdCtx<'a> {
+        /// The arguments of the command.
+        pub args: CmdArgs<'a>,
+        /// The output of the previous command of the pipeline, empty if it had none, or `None` if the command
+        /// is not piped into.
+        pub stdin: Option<&'a str>,
+    }
+
     // Macros used for command registration and argument parsing.
-    pub use crate::{console::FUNCS, parse, register};
+    pub use crate::{
+        completer,
+        console::{
+            complete::{CmpFn, COMPLETERS},
+            FUNCS,
+        },
+        parse, register, register_ctx,
+    };
 
     // Macros
 
@@ -113,7 +193,41 @@ pub mod scripting {
     #[macro_export]
     macro_rules! register {
         ($map:ident, $func:expr) => {
-            $map.insert(stringify!($func), $func as $crate::console::CmdFn)
+            $map.insert(stringify!($func), $crate::console::Cmd::Args($func))
+        };
+    }
+
+    /// Register a function taking its calling context in the console.
+    ///
+    /// This macro is used to register a function which reads the output piped into it from `CmdCtx::stdin`,
+    /// instead of getting it appended to its arguments.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// register_ctx!(FUNCS, my_function);
+    /// ```
+    #[macro_export]
+    macro_rules! register_ctx {
+        ($map:ident, $func:expr) => {
+            $map.insert(stringify!($func), $crate::console::Cmd::Ctx($func))
+        };
+    }
+
+    /// Register an argument completer for a command.
+    ///
+    /// This macro is used to register a function returning the candidates for the arguments of a command,
+    /// which are offered when tab is pressed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// completer!(COMPLETERS, my_function, my_completer);
+    /// ```
+    #[macro_export]
+    macro_rules! completer {
+        ($map:ident, $cmd:ident, $func:expr) => {
+            $map.insert(stringify!($cmd), $func as $crate::console::complete::CmpFn)
         };
     }
 }
@@ -55,6 +55,42 @@ pub mod vga {
     /// The address of the VGA buffer (only change if you know what you are doing).
     pub const BUF_ADDR: u32 = 0xb8000;
 
+    /// CRT controller address register port (only change if you know what you are doing).
+    pub const CRTC_ADDR_PORT: u16 = 0x3d4;
+
+    /// CRT controller data register port (only change if you know what you are doing).
+    pub const CRTC_DATA_PORT: u16 = 0x3d5;
+
+    /// First scanline of the hardware cursor (0 = top of the character cell).
+    pub const CURSOR_START: u8 = 14;
+
+    /// Last scanline of the hardware cursor (15 = bottom of the character cell).
+    pub const CURSOR_END: u8 = 15;
+
+    /// The maximum number of parameters of an ANSI escape sequence, further parameters are ignored.
+    pub const ANSI_MAX_PARAMS: usize = 8;
+
+    /// The number of lines kept in the scrollback history of each virtual terminal.
+    ///
+    /// The history of every terminal is allocated up front, so it takes `VT_COUNT` × 2000 × 160 bytes
+    /// (about 1.9 MiB) of the heap whether it is used or not. `mem::HEAP_SIZE` is sized for it.
+    pub const SCROLLBACK_LINES: usize = 2000;
+
+    /// The number of virtual terminals, switchable with Alt+F1 to Alt+Fn.
+    pub const VT_COUNT: usize = 6;
+
+    /// The virtual terminal kernel messages are printed to.
+    pub const LOG_VT: usize = VT_COUNT - 1;
+
+    /// The number of rows at the top of the screen reserved for the status bar (0 disables it).
+    pub const STATUS_ROWS: usize = 1;
+
+    /// The foreground color of the status bar.
+    pub const STATUS_FG: Colour = Colour::Black;
+
+    /// The background color of the status bar.
+    pub const STATUS_BG: Colour = Colour::LightGrey;
+
     /// The default foreground print color.
     pub const FG_COL: Colour = Colour::White;
 
@@ -77,6 +113,63 @@ pub mod vga {
     pub const KERNELM_COLOUR: Colour = Colour::Cyan;
 }
 
+pub mod gfx {
+    //! Graphics Configuration
+    //!
+    //! This module contains constants related to the pixel framebuffer drivers in ChadOS.
+
+    /// Physical address of the VGA graphics memory window (only change if you know what you are doing).
+    pub const VGA_FB_ADDR: u64 = 0xa0000;
+
+    /// The width of a glyph of the bitmap font in pixels.
+    pub const FONT_WIDTH: usize = 8;
+
+    /// The height of a glyph of the bitmap font in pixels (the VGA BIOS font is 8x16).
+    pub const FONT_HEIGHT: usize = 16;
+
+    /// Bochs VBE dispi index register port (only change if you know what you are doing).
+    pub const VBE_INDEX_PORT: u16 = 0x1ce;
+
+    /// Bochs VBE dispi data register port (only change if you know what you are doing).
+    pub const VBE_DATA_PORT: u16 = 0x1cf;
+
+    /// PCI vendor and device id of the Bochs/QEMU standard VGA adapter.
+    pub const VBE_PCI_ID: (u16, u16) = (0x1234, 0x1111);
+
+    /// Physical address of the linear framebuffer if the adapter is not found on the PCI bus.
+    pub const VBE_LFB_ADDR: u64 = 0xfd000000;
+
+    /// Virtual address the linear framebuffer is mapped to.
+    pub const VBE_LFB_START: u64 = 0x555555550000;
+
+    /// The default resolution of the linear framebuffer `(width, height)`.
+    pub const VBE_RES: (u16, u16) = (1024, 768);
+}
+
+pub mod pci {
+    //! PCI Configuration
+    //!
+    //! This module contains constants related to the PCI bus in ChadOS.
+
+    /// PCI configuration space address port (only change if you know what you are doing).
+    pub const CONFIG_ADDR_PORT: u16 = 0xcf8;
+
+    /// PCI configuration space data port (only change if you know what you are doing).
+    pub const CONFIG_DATA_PORT: u16 = 0xcfc;
+}
+
+pub mod fw_cfg {
+    //! Firmware Configuration
+    //!
+    //! This module contains constants related to the QEMU firmware configuration device in ChadOS.
+
+    /// Firmware configuration selector port (only change if you know what you are doing).
+    pub const FW_CFG_SEL_PORT: u16 = 0x510;
+
+    /// Firmware configuration data port (only change if you know what you are doing).
+    pub const FW_CFG_DATA_PORT: u16 = 0x511;
+}
+
 pub mod serial {
     //! Serial Configuration
     //!
@@ -86,6 +179,29 @@ pub mod serial {
     pub const SERIAL1_PORT: u16 = 0x3f8;
 }
 
+pub mod log {
+    //! Logging Configuration
+    //!
+    //! This module contains constants related to the kernel log in ChadOS.
+
+    use crate::log::Level;
+
+    /// The size of the kernel log ring buffer in bytes, the oldest records are dropped when it is full.
+    pub const LOG_BUF_SIZE: usize = 16 * 1024;
+
+    /// The maximum number of log sinks.
+    pub const LOG_SINKS: usize = 8;
+
+    /// The lowest level printed to the screen.
+    pub const VGA_LEVEL: Level = Level::Info;
+
+    /// The lowest level written to the serial port.
+    pub const SERIAL_LEVEL: Level = Level::Warn;
+
+    /// The lowest level kept in the ring buffer.
+    pub const RING_LEVEL: Level = Level::Trace;
+}
+
 pub mod interrupt {
     //! Interrupt Configuration
     //!
@@ -117,6 +233,9 @@ pub mod interrupt {
 
     /// Keyboard port (only change if you know what you are doing).
     pub const KEYBOARD_PORT: u16 = 0x60;
+
+    /// Keyboard controller status port (only change if you know what you are doing).
+    pub const KEYBOARD_STATUS_PORT: u16 = 0x64;
 }
 
 pub mod mem {
@@ -127,8 +246,12 @@ pub mod mem {
     /// The starting address of the heap.
     pub const HEAP_START: usize = 0x444444440000;
 
-    /// The size of the heap in KiB.
-    pub const HEAP_SIZE: usize = 100 * 1024;
+    /// The size of the heap in bytes.
+    ///
+    /// The scrollback history needs most of it: 2000 rows of 160 bytes are already more than 100 KiB for a
+    /// single terminal, and `vga::VT_COUNT` terminals take about 1.9 MiB, so the heap was raised from
+    /// 100 KiB to 4 MiB.
+    pub const HEAP_SIZE: usize = 4 * 1024 * 1024;
 
     /// Block sizes for the heap allocator.
     #[cfg(feature = "alloc-bump")]
@@ -148,6 +271,9 @@ pub mod time {
 
     /// PIT Hz frequency (how fast the ticks in the time tick) (only change if you know what you are doing).
     pub const PIT_HZ: u32 = 100;
+
+    /// The maximum number of tasks waiting on a timer `Delay` at the same time.
+    pub const TIMER_WAKERS: usize = 32;
 }
 
 pub mod keys {
@@ -156,7 +282,7 @@ pub mod keys {
     //! This module provides options for configuring the key layout used in ChadOS
 
     /// The supported key layout options
-    use crate::keys::WrappedLayout;
+    use crate::keys::{KeyCode, WrappedLayout};
     /// The supported layout options
     ///   Name      : Description
     ///   - De      : De 105 Key
@@ -169,6 +295,44 @@ pub mod keys {
     ///   - Dvorak  : Dvorak 104 Key
     /// The selected key layout
     pub const LAYOUT: WrappedLayout = WrappedLayout::Us;
+
+    /// The QEMU firmware configuration file overriding `LAYOUT` at boot with a layout or a compiled in
+    /// keymap, e.g. with `-fw_cfg name=opt/chados/layout,string=de`.
+    pub const LAYOUT_FW_CFG: &str = "opt/chados/layout";
+
+    /// The QEMU firmware configuration file holding a keymap description loaded at boot, e.g. with
+    /// `-fw_cfg name=opt/chados/keymap,file=src/keymaps/ch.kmap`. It takes precedence over `LAYOUT_FW_CFG`.
+    pub const KEYMAP_FW_CFG: &str = "opt/chados/keymap";
+
+    /// The maximum size of a keymap description in bytes, longer ones are truncated.
+    pub const KEYMAP_SIZE: usize = 4096;
+
+    /// The delay before a held key repeats in milliseconds, from 250 to 1000.
+    pub const REPEAT_DELAY: usize = 500;
+
+    /// The number of key repeats per second, from 2 to 30.
+    pub const REPEAT_RATE: usize = 11;
+
+    /// Whether the key repeats are generated in software instead of by the keyboard.
+    pub const SOFT_REPEAT: bool = false;
+
+    /// The key starting a compose sequence (the menu key).
+    pub const COMPOSE_KEY: KeyCode = KeyCode::Apps;
+
+    /// The maximum number of key event subscribers.
+    pub const KEY_SUBSCRIBERS: usize = 8;
+
+    /// The number of scancodes buffered until the keyboard task decodes them.
+    pub const SCANCODE_QUEUE: usize = 100;
+}
+
+pub mod mouse {
+    //! Mouse Configuration
+    //!
+    //! This module contains constants related to the PS/2 mouse in ChadOS.
+
+    /// The maximum number of mouse event subscribers.
+    pub const MOUSE_SUBSCRIBERS: usize = 8;
 }
 
 pub mod console {
@@ -189,6 +353,9 @@ pub mod console {
 
     /// Colour of the command line output.
     pub const CMD_OUT_COL: Colour = Colour::Pink;
+
+    /// Number of command lines kept in the history.
+    pub const HISTORY_SIZE: usize = 100;
 }
 
 // Tests
@@ -210,10 +377,69 @@ fn test_cfg() {
         "CFG VGA BUF_ADDR",
         assert_eq!(vga::BUF_ADDR, 0xb8000 as u32)
     );
+    test!(
+        "CFG VGA CRTC_ADDR_PORT",
+        assert_eq!(vga::CRTC_ADDR_PORT, 0x3d4 as u16)
+    );
+    test!(
+        "CFG VGA CRTC_DATA_PORT",
+        assert_eq!(vga::CRTC_DATA_PORT, 0x3d5 as u16)
+    );
+    test!(
+        "CFG VGA SCROLLBACK_LINES",
+        assert_eq!(vga::SCROLLBACK_LINES, 2000 as usize)
+    );
+    test!("CFG VGA VT_COUNT", assert_eq!(vga::VT_COUNT, 6 as usize));
+    test!("CFG VGA LOG_VT", assert_eq!(vga::LOG_VT, 5 as usize));
+    test!(
+        "CFG VGA STATUS_ROWS",
+        assert_eq!(vga::STATUS_ROWS, 1 as usize)
+    );
+    test!(
+        "CFG GFX VGA_FB_ADDR",
+        assert_eq!(gfx::VGA_FB_ADDR, 0xa0000 as u64)
+    );
+    test!(
+        "CFG GFX FONT_WIDTH",
+        assert_eq!(gfx::FONT_WIDTH, 8 as usize)
+    );
+    test!(
+        "CFG GFX FONT_HEIGHT",
+        assert_eq!(gfx::FONT_HEIGHT, 16 as usize)
+    );
+    test!(
+        "CFG GFX VBE_INDEX_PORT",
+        assert_eq!(gfx::VBE_INDEX_PORT, 0x1ce as u16)
+    );
+    test!(
+        "CFG GFX VBE_DATA_PORT",
+        assert_eq!(gfx::VBE_DATA_PORT, 0x1cf as u16)
+    );
+    test!(
+        "CFG PCI CONFIG_ADDR_PORT",
+        assert_eq!(pci::CONFIG_ADDR_PORT, 0xcf8 as u16)
+    );
+    test!(
+        "CFG PCI CONFIG_DATA_PORT",
+        assert_eq!(pci::CONFIG_DATA_PORT, 0xcfc as u16)
+    );
+    test!(
+        "CFG FW_CFG FW_CFG_SEL_PORT",
+        assert_eq!(fw_cfg::FW_CFG_SEL_PORT, 0x510 as u16)
+    );
+    test!(
+        "CFG FW_CFG FW_CFG_DATA_PORT",
+        assert_eq!(fw_cfg::FW_CFG_DATA_PORT, 0x511 as u16)
+    );
     test!(
         "CFG SERIAL SERIAL1_PORT",
         assert_eq!(serial::SERIAL1_PORT, 0x3f8 as u16)
     );
+    test!(
+        "CFG LOG LOG_BUF_SIZE",
+        assert_eq!(log::LOG_BUF_SIZE, 16384 as usize)
+    );
+    test!("CFG LOG LOG_SINKS", assert_eq!(log::LOG_SINKS, 8 as usize));
     test!(
         "CFG INTERRUPT DOUBLE_FAULT_IST_INDEX",
         assert_eq!(interrupt::DOUBLE_FAULT_IST_INDEX, 0 as u16)
@@ -250,13 +476,41 @@ fn test_cfg() {
         "CFG INTERRUPT KEYBOARD_PORT",
         assert_eq!(interrupt::KEYBOARD_PORT, 0x60 as u16)
     );
+    test!(
+        "CFG INTERRUPT KEYBOARD_STATUS_PORT",
+        assert_eq!(interrupt::KEYBOARD_STATUS_PORT, 0x64 as u16)
+    );
     test!(
         "CFG MEM HEAP_START",
         assert_eq!(mem::HEAP_START, 0x444444440000 as usize)
     );
     test!(
         "CFG MEM HEAP_SIZE",
-        assert_eq!(mem::HEAP_SIZE, 102400 as usize)
+        assert_eq!(mem::HEAP_SIZE, 4194304 as usize)
+    );
+    test!(
+        "CFG KEYS KEY_SUBSCRIBERS",
+        assert_eq!(keys::KEY_SUBSCRIBERS, 8 as usize)
+    );
+    test!(
+        "CFG KEYS SCANCODE_QUEUE",
+        assert_eq!(keys::SCANCODE_QUEUE, 100 as usize)
+    );
+    test!(
+        "CFG KEYS REPEAT_DELAY",
+        assert_eq!(keys::REPEAT_DELAY, 500 as usize)
+    );
+    test!(
+        "CFG KEYS REPEAT_RATE",
+        assert_eq!(keys::REPEAT_RATE, 11 as usize)
+    );
+    test!(
+        "CFG KEYS KEYMAP_SIZE",
+        assert_eq!(keys::KEYMAP_SIZE, 4096 as usize)
+    );
+    test!(
+        "CFG MOUSE MOUSE_SUBSCRIBERS",
+        assert_eq!(mouse::MOUSE_SUBSCRIBERS, 8 as usize)
     );
     test!(
         "CFG TIME PIT_CMD_PORT",
@@ -267,4 +521,12 @@ fn test_cfg() {
         assert_eq!(time::PIT_ADDR_PORT, 0x40 as u16)
     );
     test!("CFG TIME PIT_HZ", assert_eq!(time::PIT_HZ, 100 as u32));
+    test!(
+        "CFG TIME TIMER_WAKERS",
+        assert_eq!(time::TIMER_WAKERS, 32 as usize)
+    );
+    test!(
+        "CFG CONSOLE HISTORY_SIZE",
+        assert_eq!(console::HISTORY_SIZE, 100 as usize)
+    );
 }
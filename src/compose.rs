@@ -0,0 +1,250 @@
+//     ____ _               _  ___  ____
+//    / ___| |__   __ _  __| |/ _ \/ ___|
+//   | |   | '_ \ / _` |/ _` | | | \___ \
+//   | |___| | | | (_| | (_| | |_| |___) |
+//    \____|_| |_|\__,_|\__,_|\___/|____/
+//    https://github.com/NewDawn0/ChadOS
+//
+//   @Author: NewDawn0
+//   @Contributors: -
+//   @License: MIT
+//
+//   File: src/compose.rs
+//   Desc: Dead keys and compose sequences
+
+// RustDoc
+//! # Compose Module
+//!
+//! This module combines dead keys and compose sequences into accented characters. A dead key (e.g. `´` on
+//! the Swiss German keymap) produces nothing by itself and adds its accent to the following key, so `´` `e`
+//! types `é`. The compose key (`COMPOSE_KEY`) starts a sequence of two characters, so compose `'` `e` types
+//! `é` as well, on any layout.
+//!
+//! Both use the same table of sequences, which can be extended at runtime with `add` or the `compose`
+//! command. Dead key accents are looked up by their ASCII form, `´` as `'` and `¨` as `"`.
+//!
+//! For more information about ChadOS, visit [the ChadOS GitHub repository](https://github.com/NewDawn0/ChadOS).
+//!
+//! ## Author
+//!
+//! - [NewDawn0](https://github.com/NewDawn0)
+//!
+//! ## License
+//!
+//! This code is licensed under the MIT License. See the MIT License section below for details.
+//!
+//! # File: src/compose.rs
+//!
+//! This file defines the compose state machine for ChadOS.
+
+// Imports
+use crate::keymap::Sym;
+#[cfg(test)]
+use crate::test;
+use alloc::vec::Vec;
+use lazy_static::lazy_static;
+use spin::RwLock;
+
+// Types
+/// A compose sequence: the first and second character and the result.
+pub type Sequence = (char, char, char);
+
+/// The default sequences, grouped by the first character: the second characters and their results.
+const DEFAULT: [(char, &str, &str); 11] = [
+    ('\'', "aeiouyAEIOUYcCnNsSzZ", "áéíóúýÁÉÍÓÚÝćĆńŃśŚźŹ"),
+    ('`', "aeiouAEIOU", "àèìòùÀÈÌÒÙ"),
+    ('^', "aeiouAEIOU", "âêîôûÂÊÎÔÛ"),
+    ('"', "aeiouyAEIOUY", "äëïöüÿÄËÏÖÜŸ"),
+    ('~', "aonAON", "ãõñÃÕÑ"),
+    (',', "cC", "çÇ"),
+    ('o', "aA", "åÅ"),
+    ('/', "oO", "øØ"),
+    ('e', "aAoO", "æÆœŒ"),
+    ('s', "s", "ß"),
+    ('=', "eE", "€€"),
+];
+
+// Globals
+lazy_static! {
+    /// The compose sequences, used by dead keys and the compose key.
+    static ref TABLE: RwLock<Vec<Sequence>> = RwLock::new(
+        DEFAULT
+            .iter()
+            .flat_map(|&(first, seconds, results)| {
+                seconds
+                    .chars()
+                    .zip(results.chars())
+                    .map(move |(second, result)| (first, second, result))
+            })
+            .collect()
+    );
+}
+
+/// The state of a pending dead key or compose sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Idle,
+    /// A dead key with its accent was pressed.
+    Dead(char),
+    /// The compose key was pressed, followed by the first character if there is one.
+    Compose(Option<char>),
+}
+
+/// The compose state machine, fed with the characters typed on the keyboard.
+#[derive(Debug)]
+pub struct Compose {
+    state: State,
+}
+
+impl Compose {
+    /// Creates the state machine without a pending sequence.
+    pub const fn new() -> Self {
+        Self { state: State::Idle }
+    }
+
+    /// Starts a compose sequence, a pending one is discarded.
+    pub fn start(&mut self) {
+        self.state = State::Compose(None);
+    }
+
+    /// Feeds a character or dead key.
+    ///
+    /// Control characters cancel a pending sequence, a backspace is swallowed by it. A dead key followed by
+    /// a character it does not combine with types both, a compose sequence which does not exist types
+    /// nothing.
+    ///
+    /// # Arguments
+    ///
+    /// * `sym`: The character or dead key produced by the key.
+    ///
+    /// # Returns
+    ///
+    /// The characters to type, up to two.
+    pub fn feed(&mut self, sym: Sym) -> [Option<char>; 2] {
+        let state = core::mem::replace(&mut self.state, State::Idle);
+        let (c, dead) = match sym {
+            Sym::Char(c) => (c, false),
+            Sym::Dead(c) => (c, true),
+        };
+        match (state, dead) {
+            (State::Idle, false) => [Some(c), None],
+            (State::Idle, true) => {
+                self.state = State::Dead(c);
+                [None, None]
+            }
+            (_, false) if c == '\u{8}' => [None, None],
+            (_, false) if c.is_control() => [Some(c), None],
+            // Pressing a dead key twice or with space types the accent
+            (State::Dead(accent), true) if accent == c => [Some(accent), None],
+            (State::Dead(accent), true) => {
+                self.state = State::Dead(c);
+                [Some(accent), None]
+            }
+            (State::Dead(accent), false) if c == ' ' => [Some(accent), None],
+            (State::Dead(accent), false) => match lookup(ascii(accent), c) {
+                Some(res) => [Some(res), None],
+                None => [Some(accent), Some(c)],
+            },
+            // Dead keys type their accent within compose sequences
+            (State::Compose(None), _) => {
+                self.state = State::Compose(Some(ascii(c)));
+                [None, None]
+            }
+            (State::Compose(Some(first)), _) => [lookup(first, ascii(c)), None],
+        }
+    }
+}
+
+impl Default for Compose {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the ASCII form of a dead key accent, which is used in the compose sequences.
+fn ascii(accent: char) -> char {
+    match accent {
+        '´' => '\'',
+        '¨' => '"',
+        '¸' => ',',
+        '˜' => '~',
+        c => c,
+    }
+}
+
+/// Looks up a compose sequence, the characters may be in either order.
+///
+/// # Arguments
+///
+/// * `first`: The first character.
+/// * `second`: The second character.
+///
+/// # Returns
+///
+/// The composed character, or `None` if there is no such sequence.
+pub fn lookup(first: char, second: char) -> Option<char> {
+    let table = TABLE.read();
+    let find = |a, b| table.iter().find(|&&(x, y, _)| (x, y) == (a, b));
+    find(first, second)
+        .or_else(|| find(second, first))
+        .map(|&(_, _, res)| res)
+}
+
+/// Adds a compose sequence, replacing an existing one with the same characters.
+///
+/// # Arguments
+///
+/// * `first`: The first character, or the ASCII form of the dead key accent.
+/// * `second`: The second character.
+/// * `result`: The composed character.
+pub fn add(first: char, second: char, result: char) {
+    let mut table = TABLE.write();
+    table.retain(|&(x, y, _)| (x, y) != (first, second));
+    table.push((first, second, result));
+}
+
+/// Returns all compose sequences.
+pub fn sequences() -> Vec<Sequence> {
+    TABLE.read().clone()
+}
+
+// Tests
+#[test_case]
+fn test_compose() {
+    let mut compose = Compose::new();
+    test!(
+        "COMPOSE dead key",
+        assert_eq!(compose.feed(Sym::Dead('´')), [None, None])
+    );
+    test!(
+        "COMPOSE dead key combined",
+        assert_eq!(compose.feed(Sym::Char('e')), [Some('é'), None])
+    );
+    compose.feed(Sym::Dead('^'));
+    test!(
+        "COMPOSE dead key uncombined",
+        assert_eq!(compose.feed(Sym::Char('x')), [Some('^'), Some('x')])
+    );
+    compose.feed(Sym::Dead('~'));
+    test!(
+        "COMPOSE dead key space",
+        assert_eq!(compose.feed(Sym::Char(' ')), [Some('~'), None])
+    );
+    compose.start();
+    compose.feed(Sym::Char('/'));
+    test!(
+        "COMPOSE compose key",
+        assert_eq!(compose.feed(Sym::Char('o')), [Some('ø'), None])
+    );
+    compose.feed(Sym::Dead('¨'));
+    test!(
+        "COMPOSE backspace cancels",
+        assert_eq!(compose.feed(Sym::Char('\u{8}')), [None, None])
+    );
+    test!(
+        "COMPOSE idle",
+        assert_eq!(compose.feed(Sym::Char('u')), [Some('u'), None])
+    );
+    add('!', '!', '¡');
+    test!("COMPOSE add()", assert_eq!(lookup('!', '!'), Some('¡')));
+}
@@ -0,0 +1,127 @@
+//     ____ _               _  ___  ____
+//    / ___| |__   __ _  __| |/ _ \/ ___|
+//   | |   | '_ \ / _` |/ _` | | | \___ \
+//   | |___| | | | (_| | (_| | |_| |___) |
+//    \____|_| |_|\__,_|\__,_|\___/|____/
+//    https://github.com/NewDawn0/ChadOS
+//
+//   @Author: NewDawn0
+//   @Contributors: -
+//   @License: MIT
+//
+//   File: src/console/complete.rs
+//   Desc: CheapShell tab completion
+
+// RustDoc
+//! # Tab Completion
+//!
+//! This module completes the word before the cursor when tab is pressed. The first word of a command is
+//! completed with the names of the commands, the following words with the candidates of the completer
+//! registered for the command, if it has one. A completer gets the arguments before the word and returns all
+//! candidates, the ones starting with the word are picked here.
+//!
+//! For more information about ChadOS, visit [the ChadOS GitHub repository](https://github.com/NewDawn0/ChadOS).
+//!
+//! ## Author
+//!
+//! - [NewDawn0](https://github.com/NewDawn0)
+//!
+//! ## License
+//!
+//! This code is licensed under the MIT License. See the MIT License section below for details.
+//!
+//! # File: src/console/complete.rs
+//!
+//! This file defines the tab completion of CheapShell.
+
+// Imports
+use super::{BUILTINS, FUNCS};
+use crate::api::scripting::CmdArgs;
+use crate::cfg::console::CMD_SEPERATOR;
+#[cfg(test)]
+use crate::test;
+use alloc::{string::String, vec::Vec};
+use hashbrown::HashMap;
+use lazy_static::lazy_static;
+use spin::RwLock;
+
+// Types
+/// A function returning the candidates for an argument of a command, given the arguments before it.
+pub type CmpFn = fn(CmdArgs) -> Vec<String>;
+
+// Globals
+lazy_static! {
+    /// A read-write lock containing a map of command names to the completers of their arguments.
+    pub static ref COMPLETERS: RwLock<HashMap<&'static str, CmpFn>> = RwLock::new(HashMap::new());
+}
+
+/// Returns the candidates for the word at the end of a command line.
+///
+/// # Arguments
+///
+/// * `line`: The command line up to the cursor.
+///
+/// # Returns
+///
+/// The word being completed and the candidates starting with it, sorted.
+pub fn candidates(line: &str) -> (&str, Vec<String>) {
+    let cmd = line
+        .rsplit([CMD_SEPERATOR, ';', '&', '|'])
+        .next()
+        .unwrap_or_default();
+    let word = cmd.rsplit(char::is_whitespace).next().unwrap_or_default();
+    let before = cmd[..cmd.len() - word.len()]
+        .split_whitespace()
+        .collect::<Vec<_>>();
+    let mut res = match before.split_first() {
+        None => FUNCS
+            .read()
+            .keys()
+            .chain(BUILTINS.iter())
+            .map(|&name| String::from(name))
+            .collect(),
+        Some((name, args)) => match COMPLETERS.read().get(name) {
+            Some(completer) => completer(args),
+            None => Vec::new(),
+        },
+    };
+    res.retain(|candidate| candidate.starts_with(word));
+    res.sort_unstable();
+    res.dedup();
+    (word, res)
+}
+
+/// Returns the longest prefix shared by all candidates.
+pub fn common_prefix(candidates: &[String]) -> &str {
+    let Some((first, rest)) = candidates.split_first() else {
+        return "";
+    };
+    let len = rest.iter().fold(first.len(), |len, candidate| {
+        first[..len]
+            .char_indices()
+            .zip(candidate.chars())
+            .find(|&((_, a), b)| a != b)
+            .map_or(len.min(candidate.len()), |((i, _), _)| i)
+    });
+    &first[..len]
+}
+
+// Tests
+#[test_case]
+fn test_complete() {
+    let (word, res) = candidates("uptime ! li");
+    test!("COMPLETE candidates() word", assert_eq!(word, "li"));
+    test!(
+        "COMPLETE candidates() builtin",
+        assert!(res.iter().any(|c| c == "list"))
+    );
+    test!(
+        "COMPLETE candidates() no completer",
+        assert!(candidates("list x").1.is_empty())
+    );
+    let res = [String::from("layout"), String::from("later")];
+    test!(
+        "COMPLETE common_prefix()",
+        assert_eq!(common_prefix(&res), "la")
+    );
+}
@@ -0,0 +1,455 @@
+//     ____ _               _  ___  ____
+//    / ___| |__   __ _  __| |/ _ \/ ___|
+//   | |   | '_ \ / _` |/ _` | | | \___ \
+//   | |___| | | | (_| | (_| | |_| |___) |
+//    \____|_| |_|\__,_|\__,_|\___/|____/
+//    https://github.com/NewDawn0/ChadOS
+//
+//   @Author: NewDawn0
+//   @Contributors: -
+//   @License: MIT
+//
+//   File: src/console/editor.rs
+//   Desc: CheapShell line editor
+
+// RustDoc
+//! # Line Editor
+//!
+//! This module provides the line editor of CheapShell. The command line can be edited anywhere, not only at
+//! its end: the cursor moves with the arrow keys, Home and End, characters are inserted at the cursor and
+//! the changed part of the line is redrawn, also when it wraps onto the following rows. The editor only
+//! touches the screen after the prompt, so it can never erase the prompt.
+//!
+//! Up and down recall the lines in the history, and Ctrl+R searches it backwards for the text typed
+//! afterwards, the line found replaces the command line when the search is left.
+//!
+//! For more information about ChadOS, visit [the ChadOS GitHub repository](https://github.com/NewDawn0/ChadOS).
+//!
+//! ## Author
+//!
+//! - [NewDawn0](https://github.com/NewDawn0)
+//!
+//! ## License
+//!
+//! This code is licensed under the MIT License. See the MIT License section below for details.
+//!
+//! # File: src/console/editor.rs
+//!
+//! This file defines the line editor of CheapShell.
+
+// Imports
+use super::history::HISTORY;
+#[cfg(test)]
+use crate::test;
+use crate::{
+    cfg::vga::{BUFFER_HEIGHT, BUFFER_WIDTH},
+    io::vga::{cursor, set_cursor},
+    print,
+};
+use alloc::{format, string::String, vec::Vec};
+
+/// A command line being edited.
+#[derive(Debug, Default)]
+pub struct Editor {
+    line: Vec<char>,
+    /// The index of the character under the cursor.
+    cursor: usize,
+    /// The screen position of the first character of the line, right after the prompt.
+    start: (usize, usize),
+    /// The index of the history line shown, and the line typed before it was recalled.
+    recall: Option<(usize, String)>,
+    search: Option<Search>,
+}
+
+/// An incremental reverse search through the history.
+#[derive(Debug)]
+struct Search {
+    query: String,
+    /// The index of the history line found.
+    found: Option<usize>,
+    /// Whether the query was not found, the previous line found is kept.
+    failed: bool,
+    /// The command line before the search started.
+    line: String,
+}
+
+impl Editor {
+    /// Returns the command line.
+    pub fn text(&self) -> String {
+        self.line.iter().collect()
+    }
+
+    /// Returns the command line up to the cursor.
+    pub fn before(&self) -> String {
+        self.line[..self.cursor].iter().collect()
+    }
+
+    /// Returns whether the command line is empty.
+    pub fn is_empty(&self) -> bool {
+        self.line.is_empty()
+    }
+
+    /// Starts an empty command line at the screen cursor, which has to be right after the prompt.
+    pub fn begin(&mut self) {
+        self.line.clear();
+        self.cursor = 0;
+        self.start = cursor();
+        self.recall = None;
+        self.search = None;
+    }
+
+    /// Moves the start of an empty command line to the screen cursor, the history recall is kept.
+    pub fn anchor(&mut self) {
+        self.start = cursor();
+    }
+
+    /// Draws the whole command line again at the screen cursor, e.g. after the screen was cleared.
+    pub fn redisplay(&mut self) {
+        self.start = cursor();
+        self.redraw(0, 0);
+        self.place(self.cursor);
+    }
+
+    /// Moves the screen cursor behind the command line, so the following output starts after it.
+    pub fn finish(&mut self) {
+        self.place(self.line.len());
+    }
+
+    /// Inserts a character at the cursor.
+    pub fn insert(&mut self, c: char) {
+        self.recall = None;
+        self.line.insert(self.cursor, c);
+        self.cursor += 1;
+        self.redraw(self.cursor - 1, 0);
+        self.place(self.cursor);
+    }
+
+    /// Removes the character before the cursor.
+    pub fn backspace(&mut self) {
+        if self.cursor > 0 {
+            self.recall = None;
+            self.cursor -= 1;
+            self.line.remove(self.cursor);
+            self.redraw(self.cursor, 1);
+            self.place(self.cursor);
+        }
+    }
+
+    /// Removes the character under the cursor.
+    pub fn delete(&mut self) {
+        if self.cursor < self.line.len() {
+            self.recall = None;
+            self.line.remove(self.cursor);
+            self.redraw(self.cursor, 1);
+            self.place(self.cursor);
+        }
+    }
+
+    /// Moves the cursor one character to the left.
+    pub fn left(&mut self) {
+        self.move_to(self.cursor.saturating_sub(1));
+    }
+
+    /// Moves the cursor one character to the right.
+    pub fn right(&mut self) {
+        self.move_to(self.cursor + 1);
+    }
+
+    /// Moves the cursor to the start of the line.
+    pub fn home(&mut self) {
+        self.move_to(0);
+    }
+
+    /// Moves the cursor to the end of the line.
+    pub fn end(&mut self) {
+        self.move_to(self.line.len());
+    }
+
+    /// Removes everything from the cursor to the end of the line.
+    pub fn kill_end(&mut self) {
+        let removed = self.line.len() - self.cursor;
+        if removed > 0 {
+            self.recall = None;
+        }
+        self.line.truncate(self.cursor);
+        self.redraw(self.cursor, removed);
+        self.place(self.cursor);
+    }
+
+    /// Removes everything from the start of the line to the cursor.
+    pub fn kill_start(&mut self) {
+        self.kill_to(0);
+    }
+
+    /// Removes the word before the cursor, with the spaces following it.
+    pub fn kill_word(&mut self) {
+        let before = &self.line[..self.cursor];
+        let end = before.iter().rposition(|c| !c.is_whitespace());
+        let start = end
+            .and_then(|end| before[..end].iter().rposition(|c| c.is_whitespace()))
+            .map_or(0, |space| space + 1);
+        self.kill_to(start);
+    }
+
+    /// Replaces the command line with the previous line in the history.
+    pub fn up(&mut self) {
+        let history = HISTORY.read();
+        let (index, typed) = match self.recall.take() {
+            Some((index, typed)) => (index.saturating_sub(1), typed),
+            None if history.len() > 0 => (history.len() - 1, self.text()),
+            None => return,
+        };
+        let line = String::from(history.get(index).unwrap_or_default());
+        drop(history);
+        self.replace(&line);
+        self.recall = Some((index, typed));
+    }
+
+    /// Replaces the command line with the next line in the history, or the line typed before the recall.
+    pub fn down(&mut self) {
+        let Some((index, typed)) = self.recall.take() else {
+            return;
+        };
+        let next = HISTORY.read().get(index + 1).map(String::from);
+        match next {
+            Some(line) => {
+                self.replace(&line);
+                self.recall = Some((index + 1, typed));
+            }
+            None => self.replace(&typed),
+        }
+    }
+
+    /// Returns whether a reverse search is in progress.
+    pub fn searching(&self) -> bool {
+        self.search.is_some()
+    }
+
+    /// Starts a reverse search, or searches for an older line if one is in progress.
+    pub fn search(&mut self) {
+        match self.search {
+            Some(ref search) => {
+                let before = search.found.unwrap_or(HISTORY.read().len());
+                self.find(before);
+            }
+            None => {
+                self.search = Some(Search {
+                    query: String::new(),
+                    found: None,
+                    failed: false,
+                    line: self.text(),
+                });
+                self.show();
+            }
+        }
+    }
+
+    /// Adds a character to the query of the reverse search.
+    pub fn search_insert(&mut self, c: char) {
+        if let Some(ref mut search) = self.search {
+            search.query.push(c);
+            // The line found may still contain the longer query
+            let before = search.found.map_or(HISTORY.read().len(), |index| index + 1);
+            self.find(before);
+        }
+    }
+
+    /// Removes the last character from the query of the reverse search.
+    pub fn search_backspace(&mut self) {
+        if let Some(ref mut search) = self.search {
+            search.query.pop();
+            search.found = None;
+            self.find(HISTORY.read().len());
+        }
+    }
+
+    /// Ends the reverse search, the line found becomes the command line.
+    pub fn accept(&mut self) {
+        if let Some(search) = self.search.take() {
+            let found = search
+                .found
+                .and_then(|index| HISTORY.read().get(index).map(String::from));
+            self.replace(&found.unwrap_or(search.line));
+        }
+    }
+
+    /// Ends the reverse search, the command line is restored.
+    pub fn cancel_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            self.replace(&search.line);
+        }
+    }
+
+    /// Searches the history for the query of the reverse search and shows the result.
+    ///
+    /// # Arguments
+    ///
+    /// * `before`: The index to search before.
+    fn find(&mut self, before: usize) {
+        if let Some(ref mut search) = self.search {
+            let found = match search.query.is_empty() {
+                true => None,
+                false => HISTORY.read().search(&search.query, before),
+            };
+            search.failed = found.is_none() && !search.query.is_empty();
+            search.found = found.or(search.found);
+        }
+        self.show();
+    }
+
+    /// Shows the query of the reverse search and the line found in place of the command line.
+    fn show(&mut self) {
+        if let Some(ref search) = self.search {
+            let history = HISTORY.read();
+            let found = search.found.and_then(|index| history.get(index));
+            let text = format!(
+                "({}reverse-i-search)`{}': {}",
+                if search.failed { "failed " } else { "" },
+                search.query,
+                found.unwrap_or_default()
+            );
+            drop(history);
+            self.replace(&text);
+        }
+    }
+
+    /// Replaces the command line with a text, the cursor moves to its end.
+    fn replace(&mut self, text: &str) {
+        let removed = self.line.len();
+        self.line = text.chars().collect();
+        self.cursor = self.line.len();
+        self.redraw(0, removed.saturating_sub(self.line.len()));
+        self.place(self.cursor);
+    }
+
+    /// Removes everything from an index to the cursor and moves the cursor there.
+    fn kill_to(&mut self, index: usize) {
+        let removed = self.cursor - index;
+        if removed > 0 {
+            self.recall = None;
+        }
+        self.line.drain(index..self.cursor);
+        self.cursor = index;
+        self.redraw(index, removed);
+        self.place(index);
+    }
+
+    /// Moves the cursor to an index of the line, it is clamped to the end of the line.
+    fn move_to(&mut self, index: usize) {
+        self.cursor = index.min(self.line.len());
+        self.place(self.cursor);
+    }
+
+    /// Draws the line from an index to its end.
+    ///
+    /// # Arguments
+    ///
+    /// * `from`: The index of the first character to draw.
+    /// * `erase`: The number of cells to blank after the line, where removed characters were drawn.
+    fn redraw(&mut self, from: usize, erase: usize) {
+        let end = self.line.len() + erase;
+        if from >= end {
+            return;
+        }
+        self.place(from);
+        let tail = self.line[from..].iter().collect::<String>();
+        print!("{}{:2$}", tail, "", erase);
+        // Output wrapping past the bottom row scrolled the screen, and the line with it
+        let (row, _) = cursor();
+        let expected = self.start.0 + (self.start.1 + end - 1) / BUFFER_WIDTH;
+        self.start.0 = self.start.0.saturating_sub(expected.saturating_sub(row));
+    }
+
+    /// Moves the screen cursor to an index of the line.
+    fn place(&mut self, index: usize) {
+        let (row, col) = position(self.start, index);
+        if row >= BUFFER_HEIGHT {
+            // The cursor is right after a line ending in the bottom right corner, scroll to make room
+            let scroll = row - (BUFFER_HEIGHT - 1);
+            set_cursor(BUFFER_HEIGHT - 1, 0);
+            for _ in 0..scroll {
+                print!("\n");
+            }
+            self.start.0 = self.start.0.saturating_sub(scroll);
+        }
+        set_cursor(row.min(BUFFER_HEIGHT - 1), col);
+    }
+}
+
+/// Returns the screen position of an index of a line.
+///
+/// # Arguments
+///
+/// * `start`: The screen position of the first character.
+/// * `index`: The index of the character.
+fn position(start: (usize, usize), index: usize) -> (usize, usize) {
+    let cell = start.1 + index;
+    (start.0 + cell / BUFFER_WIDTH, cell % BUFFER_WIDTH)
+}
+
+// Tests
+#[test_case]
+fn test_editor() {
+    let (row, col) = cursor();
+    set_cursor(row, 0);
+    let mut editor = Editor::default();
+    editor.begin();
+    for c in "echo wrld".chars() {
+        editor.insert(c);
+    }
+    editor.left();
+    editor.left();
+    editor.left();
+    editor.insert('o');
+    test!("EDITOR insert()", assert_eq!(editor.text(), "echo world"));
+    editor.home();
+    editor.delete();
+    editor.right();
+    editor.backspace();
+    test!("EDITOR delete()", assert_eq!(editor.text(), "ho world"));
+    editor.end();
+    editor.kill_word();
+    test!("EDITOR kill_word()", assert_eq!(editor.text(), "ho "));
+    editor.home();
+    editor.backspace();
+    test!(
+        "EDITOR backspace() at start",
+        assert_eq!(editor.text(), "ho ")
+    );
+    editor.kill_end();
+    test!("EDITOR kill_end()", assert!(editor.is_empty()));
+    HISTORY.write().push("editor test 1");
+    HISTORY.write().push("editor test 2");
+    editor.up();
+    editor.up();
+    editor.down();
+    test!(
+        "EDITOR up() down()",
+        assert_eq!(editor.text(), "editor test 2")
+    );
+    editor.kill_end();
+    editor.up();
+    test!(
+        "EDITOR up() kept after no edit",
+        assert_eq!(editor.text(), "editor test 1")
+    );
+    editor.backspace();
+    editor.up();
+    test!(
+        "EDITOR up() reset after edit",
+        assert_eq!(editor.text(), "editor test 2")
+    );
+    editor.down();
+    test!(
+        "EDITOR down() typed",
+        assert_eq!(editor.text(), "editor test ")
+    );
+    editor.kill_start();
+    test!(
+        "EDITOR position() wrap",
+        assert_eq!(position((3, 2), BUFFER_WIDTH), (4, 2))
+    );
+    editor.finish();
+    crate::println!();
+    set_cursor(cursor().0, col);
+}
@@ -0,0 +1,245 @@
+//     ____ _               _  ___  ____
+//    / ___| |__   __ _  __| |/ _ \/ ___|
+//   | |   | '_ \ / _` |/ _` | | | \___ \
+//   | |___| | | | (_| | (_| | |_| |___) |
+//    \____|_| |_|\__,_|\__,_|\___/|____/
+//    https://github.com/NewDawn0/ChadOS
+//
+//   @Author: NewDawn0
+//   @Contributors: -
+//   @License: MIT
+//
+//   File: src/console/history.rs
+//   Desc: CheapShell command history
+
+// RustDoc
+//! # Command History
+//!
+//! This module keeps the last `HISTORY_SIZE` command lines submitted on any terminal. Every line gets a
+//! number when it is added, which stays the same when older lines are dropped, so `!n` always refers to the
+//! line `history` listed as `n`.
+//!
+//! Before a line is run, `!!` is replaced with the previous line, `!n` with line `n` and `!-n` with the
+//! `n`th previous line. A `!` which is not followed by `!`, a number or `-` and a number is left alone, as it
+//! separates the commands of a pipeline, and so is one in single quotes or after a backslash.
+//!
+//! For more information about ChadOS, visit [the ChadOS GitHub repository](https://github.com/NewDawn0/ChadOS).
+//!
+//! ## Author
+//!
+//! - [NewDawn0](https://github.com/NewDawn0)
+//!
+//! ## License
+//!
+//! This code is licensed under the MIT License. See the MIT License section below for details.
+//!
+//! # File: src/console/history.rs
+//!
+//! This file defines the command history of CheapShell.
+
+// Imports
+use crate::cfg::console::HISTORY_SIZE;
+#[cfg(test)]
+use crate::test;
+use alloc::{collections::VecDeque, format, string::String};
+use lazy_static::lazy_static;
+use spin::RwLock;
+
+// Globals
+lazy_static! {
+    /// The command lines submitted on all terminals.
+    pub static ref HISTORY: RwLock<History> = RwLock::new(History::new());
+}
+
+/// A bounded list of command lines, the oldest ones are dropped first.
+#[derive(Debug)]
+pub struct History {
+    lines: VecDeque<String>,
+    /// The number of the oldest line.
+    first: usize,
+}
+
+impl History {
+    /// Creates an empty history, the first line added is number 1.
+    pub fn new() -> Self {
+        Self {
+            lines: VecDeque::new(),
+            first: 1,
+        }
+    }
+
+    /// Adds a command line. Empty lines and repetitions of the previous line are not added.
+    pub fn push(&mut self, line: &str) {
+        let line = line.trim();
+        if line.is_empty() || self.lines.back().is_some_and(|last| last == line) {
+            return;
+        }
+        self.lines.push_back(String::from(line));
+        if self.lines.len() > HISTORY_SIZE {
+            self.lines.pop_front();
+            self.first += 1;
+        }
+    }
+
+    /// Removes all lines, the numbers keep counting.
+    pub fn clear(&mut self) {
+        self.first += self.lines.len();
+        self.lines.clear();
+    }
+
+    /// Returns the number of lines.
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Returns a line by its index, 0 being the oldest line.
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.lines.get(index).map(String::as_str)
+    }
+
+    /// Returns the lines with their numbers, oldest first.
+    pub fn numbered(&self) -> impl Iterator<Item = (usize, &str)> {
+        (self.first..).zip(self.lines.iter().map(String::as_str))
+    }
+
+    /// Searches for the newest line containing a text.
+    ///
+    /// # Arguments
+    ///
+    /// * `query`: The text to search for.
+    /// * `before`: The index to search before, only older lines are searched.
+    ///
+    /// # Returns
+    ///
+    /// The index of the line, or `None` if no older line contains the text.
+    pub fn search(&self, query: &str, before: usize) -> Option<usize> {
+        self.lines
+            .iter()
+            .take(before)
+            .rposition(|line| line.contains(query))
+    }
+
+    /// Replaces the history references in a command line.
+    ///
+    /// # Arguments
+    ///
+    /// * `line`: The command line to expand.
+    ///
+    /// # Returns
+    ///
+    /// The expanded line, `Ok(None)` if it contains no references, or an error naming a reference to a line
+    /// which is not in the history.
+    pub fn expand(&self, line: &str) -> Result<Option<String>, String> {
+        let mut res = String::new();
+        let mut expanded = false;
+        let (mut single, mut double, mut escaped) = (false, false, false);
+        let mut chars = line.char_indices();
+        while let Some((pos, c)) = chars.next() {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' if !single => escaped = true,
+                '\'' if !double => single = !single,
+                '"' if !single => double = !double,
+                '!' if !single => {
+                    if let Some((event, index)) = self.event(&line[pos + 1..]) {
+                        match index.and_then(|index| self.get(index)) {
+                            Some(found) => res.push_str(found),
+                            None => return Err(format!("!{}: event not found", event)),
+                        }
+                        expanded = true;
+                        // The event is ASCII
+                        chars.nth(event.len() - 1);
+                        continue;
+                    }
+                }
+                _ => {}
+            }
+            res.push(c);
+        }
+        Ok(expanded.then_some(res))
+    }
+
+    /// Reads a history reference after a `!`.
+    ///
+    /// # Arguments
+    ///
+    /// * `after`: The command line after the `!`.
+    ///
+    /// # Returns
+    ///
+    /// The reference without the `!` and the index of the line it refers to, or `None` if there is no
+    /// reference.
+    fn event<'a>(&self, after: &'a str) -> Option<(&'a str, Option<usize>)> {
+        if after.starts_with('!') {
+            return Some((&after[..1], self.len().checked_sub(1)));
+        }
+        let (back, digits) = match after.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, after),
+        };
+        let len = digits.len()
+            - digits
+                .trim_start_matches(|c: char| c.is_ascii_digit())
+                .len();
+        if len == 0 {
+            return None;
+        }
+        let n = digits[..len].parse::<usize>().unwrap_or(0);
+        let index = match back {
+            true => self.len().checked_sub(n),
+            false => n.checked_sub(self.first),
+        };
+        Some((&after[..len + back as usize], index))
+    }
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Tests
+#[test_case]
+fn test_history() {
+    let mut history = History::new();
+    history.push("uptime");
+    history.push("echo hi");
+    history.push("echo hi");
+    history.push("  ");
+    test!("HISTORY push()", assert_eq!(history.len(), 2));
+    test!(
+        "HISTORY expand() !!",
+        assert_eq!(history.expand("!! ! sum"), Ok(Some("echo hi ! sum".into())))
+    );
+    test!(
+        "HISTORY expand() !n",
+        assert_eq!(history.expand("!1"), Ok(Some("uptime".into())))
+    );
+    test!(
+        "HISTORY expand() !-n",
+        assert_eq!(history.expand("!-2"), Ok(Some("uptime".into())))
+    );
+    test!(
+        "HISTORY expand() none",
+        assert_eq!(history.expand("a ! b"), Ok(None))
+    );
+    test!(
+        "HISTORY expand() quoted",
+        assert_eq!(history.expand("echo '!!' \\!1"), Ok(None))
+    );
+    test!(
+        "HISTORY expand() missing",
+        assert!(history.expand("!7").is_err())
+    );
+    test!(
+        "HISTORY search()",
+        assert_eq!(history.search("up", 2), Some(0))
+    );
+    history.clear();
+    history.push("clear");
+    test!(
+        "HISTORY numbered()",
+        assert_eq!(history.numbered().next(), Some((3, "clear")))
+    );
+}
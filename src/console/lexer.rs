@@ -0,0 +1,252 @@
+//     ____ _               _  ___  ____
+//    / ___| |__   __ _  __| |/ _ \/ ___|
+//   | |   | '_ \ / _` |/ _` | | | \___ \
+//   | |___| | | | (_| | (_| | |_| |___) |
+//    \____|_| |_|\__,_|\__,_|\___/|____/
+//    https://github.com/NewDawn0/ChadOS
+//
+//   @Author: NewDawn0
+//   @Contributors: -
+//   @License: MIT
+//
+//   File: src/console/lexer.rs
+//   Desc: CheapShell tokenizer
+
+// RustDoc
+//! # Lexer
+//!
+//! This module splits a command line into words and operators. Words are separated by whitespace,
+//! `CMD_SEPERATOR` separates the commands of a pipeline and `;`, `&&` and `||` the pipelines of a list.
+//! Quotes and backslashes keep them from doing so:
+//!
+//! - `'...'` is taken literally.
+//! - `"..."` expands variables, a backslash only escapes `"`, `\` and `$` in it.
+//! - `\c` outside of quotes is the character `c`.
+//! - `$NAME` and `${NAME}` are replaced with the value of a variable, `$?` with the exit status of the
+//!   last command. Unset variables are empty, and values are not split into words.
+//!
+//! For more information about ChadOS, visit [the ChadOS GitHub repository](https://github.com/NewDawn0/ChadOS).
+//!
+//! ## Author
+//!
+//! - [NewDawn0](https://github.com/NewDawn0)
+//!
+//! ## License
+//!
+//! This code is licensed under the MIT License. See the MIT License section below for details.
+//!
+//! # File: src/console/lexer.rs
+//!
+//! This file defines the tokenizer of CheapShell.
+
+// Imports
+use crate::cfg::console::CMD_SEPERATOR;
+#[cfg(test)]
+use crate::test;
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{iter::Peekable, str::Chars};
+
+/// A word or operator of a command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token {
+    Word(String),
+    /// `CMD_SEPERATOR`, which pipes the output of a command into the next one.
+    Pipe,
+    /// `;`, which runs the next pipeline after this one.
+    Seq,
+    /// `&&`, which runs the next pipeline if this one succeeded.
+    And,
+    /// `||`, which runs the next pipeline if this one failed.
+    Or,
+}
+
+/// Splits a command line into tokens, expanding the variables in it.
+///
+/// # Arguments
+///
+/// * `line`: The command line.
+/// * `var`: Returns the value of a variable by its name, `?` being the exit status of the last command.
+///
+/// # Returns
+///
+/// The tokens, or an error if a quote or `${` is not closed.
+pub fn tokenize(line: &str, var: impl Fn(&str) -> Option<String>) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    // Empty quotes start a word too
+    let mut started = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => end(&mut tokens, &mut word, &mut started),
+            CMD_SEPERATOR => {
+                end(&mut tokens, &mut word, &mut started);
+                tokens.push(Token::Pipe);
+            }
+            ';' => {
+                end(&mut tokens, &mut word, &mut started);
+                tokens.push(Token::Seq);
+            }
+            // A single & or | is an ordinary character
+            '&' | '|' if chars.next_if_eq(&c).is_some() => {
+                end(&mut tokens, &mut word, &mut started);
+                tokens.push(match c {
+                    '&' => Token::And,
+                    _ => Token::Or,
+                });
+            }
+            '\\' => {
+                word.push(chars.next().ok_or("Unexpected end of line after \\")?);
+                started = true;
+            }
+            '\'' => {
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err("Unterminated '".to_string()),
+                    }
+                }
+                started = true;
+            }
+            '"' => {
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.peek() {
+                            Some(&c @ ('"' | '\\' | '$')) => {
+                                chars.next();
+                                word.push(c);
+                            }
+                            _ => word.push('\\'),
+                        },
+                        Some('$') => word.push_str(&variable(&mut chars, &var)?),
+                        Some(c) => word.push(c),
+                        None => return Err("Unterminated \"".to_string()),
+                    }
+                }
+                started = true;
+            }
+            '$' => {
+                let value = variable(&mut chars, &var)?;
+                started |= !value.is_empty();
+                word.push_str(&value);
+            }
+            c => {
+                word.push(c);
+                started = true;
+            }
+        }
+    }
+    end(&mut tokens, &mut word, &mut started);
+    Ok(tokens)
+}
+
+/// Adds the word read so far to the tokens, if one was started.
+fn end(tokens: &mut Vec<Token>, word: &mut String, started: &mut bool) {
+    if core::mem::take(started) {
+        tokens.push(Token::Word(core::mem::take(word)));
+    }
+}
+
+/// Reads the name of a variable after a `$` and returns its value.
+///
+/// A `$` which is not followed by `?`, `{` or a name is returned as it is.
+///
+/// # Arguments
+///
+/// * `chars`: The characters after the `$`.
+/// * `var`: Returns the value of a variable by its name.
+fn variable(
+    chars: &mut Peekable<Chars>,
+    var: &impl Fn(&str) -> Option<String>,
+) -> Result<String, String> {
+    let is_name = |c: &char| c.is_ascii_alphanumeric() || *c == '_';
+    let name = match chars.peek() {
+        Some('?') => {
+            chars.next();
+            String::from("?")
+        }
+        Some('{') => {
+            chars.next();
+            let mut name = String::new();
+            loop {
+                match chars.next() {
+                    Some('}') => break name,
+                    Some(c) => name.push(c),
+                    None => return Err("Unterminated ${".to_string()),
+                }
+            }
+        }
+        Some(c) if is_name(c) => {
+            let mut name = String::new();
+            while let Some(c) = chars.next_if(is_name) {
+                name.push(c);
+            }
+            name
+        }
+        _ => return Ok(String::from("$")),
+    };
+    Ok(var(&name).unwrap_or_default())
+}
+
+// Tests
+#[test_case]
+fn test_lexer() {
+    let var = |name: &str| match name {
+        "A" => Some(String::from("x y")),
+        "?" => Some(String::from("1")),
+        _ => None,
+    };
+    let words = |line| {
+        tokenize(line, var).map(|tokens| {
+            tokens
+                .into_iter()
+                .map(|token| match token {
+                    Token::Word(word) => word,
+                    Token::Pipe => String::from("!"),
+                    Token::Seq => String::from(";"),
+                    Token::And => String::from("&&"),
+                    Token::Or => String::from("||"),
+                })
+                .collect::<Vec<_>>()
+        })
+    };
+    test!(
+        "LEXER tokenize() quotes",
+        assert_eq!(
+            words("echo \"a ! b\" 'c$A' ''"),
+            Ok(["echo", "a ! b", "c$A", ""].map(String::from).to_vec())
+        )
+    );
+    test!(
+        "LEXER tokenize() pipe",
+        assert_eq!(
+            words("a\\ b!c"),
+            Ok(["a b", "!", "c"].map(String::from).to_vec())
+        )
+    );
+    test!(
+        "LEXER tokenize() operators",
+        assert_eq!(
+            words("a;b&&c||d & e ';'"),
+            Ok(["a", ";", "b", "&&", "c", "||", "d", "&", "e", ";"]
+                .map(String::from)
+                .to_vec())
+        )
+    );
+    test!(
+        "LEXER tokenize() variables",
+        assert_eq!(
+            words("$A ${A}$? $B \"$B\" $"),
+            Ok(["x y", "x y1", "", "$"].map(String::from).to_vec())
+        )
+    );
+    test!(
+        "LEXER tokenize() unterminated",
+        assert!(words("echo 'a").is_err())
+    );
+}
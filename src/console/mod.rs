@@ -0,0 +1,540 @@
+//     ____ _               _  ___  ____
+//    / ___| |__   __ _  __| |/ _ \/ ___|
+//   | |   | '_ \ / _` |/ _` | | | \___ \
+//   | |___| | | | (_| | (_| | |_| |___) |
+//    \____|_| |_|\__,_|\__,_|\___/|____/
+//    https://github.com/NewDawn0/ChadOS
+//
+//   @Author: NewDawn0
+//   @Contributors: -
+//   @License: MIT
+//
+//   File: src/console/mod.rs
+//   Desc: CheapShell implemenation
+
+// RustDoc
+//! # ChadOS Console
+//!
+//! This module provides the implementation of ChadOS's console and shell, including custom command handling and I/O.
+//!
+//! For more information about ChadOS, visit [the ChadOS GitHub repository](https://github.com/NewDawn0/ChadOS).
+//!
+//! ## Author
+//!
+//! - [NewDawn0](https://github.com/NewDawn0)
+//!
+//! ## License
+//!
+//! This code is licensed under the MIT License.
+//!
+//! # File: src/console/mod.rs
+//!
+//! This file contains the implementation of ChadOS's custom console and shell, including command handling and I/O.
+//!
+//! ## Usage
+//!
+//! To use the ChadOS console and shell (CheapShell), call the `init` function to set up the console and start accepting user input.
+//!
+//! ```rust
+//! use chados::console::init;
+//!
+//! fn main() {
+//!     init();
+//! }
+//! ```
+
+// Imports
+#[cfg(test)]
+use crate::test;
+use crate::{
+    api::scripting::{CmdArgs, CmdCtx, CmdErr, CmdRes, CtxRes, ExitCode},
+    cfg::{
+        console::{CMD_ERR_COL, CMD_OK_COL, CMD_OUT_COL},
+        vga::{LOG_VT, VT_COUNT},
+    },
+    io::vga::clear_all,
+    io::vga::prelude::*,
+    io::vt,
+    keys::{KeyCode, Modifiers, KEY_HANDLER},
+    usr_bin,
+};
+use alloc::{
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::sync::atomic::{AtomicU8, Ordering};
+use hashbrown::HashMap;
+use lazy_static::lazy_static;
+use spin::RwLock;
+
+// Modules
+pub mod complete;
+mod editor;
+pub mod history;
+mod lexer;
+use editor::Editor;
+use history::HISTORY;
+use lexer::Token;
+
+// Types
+pub type CmdFn = fn(CmdArgs) -> CmdRes;
+pub type CtxFn = fn(&CmdCtx) -> CtxRes;
+
+/// A command registered in the console.
+#[derive(Clone, Copy)]
+pub enum Cmd {
+    /// A command taking only arguments, the output piped into it is appended to them word by word.
+    Args(CmdFn),
+    /// A command taking its calling context, the output piped into it is its stdin.
+    Ctx(CtxFn),
+}
+
+impl Cmd {
+    /// Calls the command.
+    pub fn call(self, ctx: &CmdCtx) -> CtxRes {
+        match self {
+            Cmd::Args(func) => match ctx.stdin {
+                Some(stdin) => {
+                    let mut args = ctx.args.to_vec();
+                    args.extend(stdin.split_whitespace());
+                    Ok(func(&args)?)
+                }
+                None => Ok(func(ctx.args)?),
+            },
+            Cmd::Ctx(func) => func(ctx),
+        }
+    }
+}
+
+/// The commands built into the shell.
+const BUILTINS: [&str; 7] = ["list", "set", "unset", "env", "true", "false", "test"];
+
+/// The exit status of a command which does not exist.
+const NOT_FOUND: ExitCode = 127;
+
+/// The exit status of a command line which cannot be parsed, or a builtin used wrongly.
+const USAGE: ExitCode = 2;
+
+// Globals
+/// The exit status of the last command.
+static STATUS: AtomicU8 = AtomicU8::new(0);
+lazy_static! {
+    /// A read-write lock containing a map of command names to their corresponding functions.
+    pub static ref FUNCS: RwLock<HashMap<&'static str, Cmd>> = RwLock::new(HashMap::new());
+
+    /// A read-write lock containing the shell variables, which `$NAME` expands to.
+    pub static ref ENV: RwLock<BTreeMap<String, String>> = RwLock::new(BTreeMap::new());
+
+    /// A read-write lock containing the current command line of every virtual terminal.
+    pub static ref CMD_LINE: RwLock<[Editor; VT_COUNT]> = RwLock::new(Default::default());
+}
+
+/// Initializes the ChadOS console and shell.
+///
+/// This function sets up the console and starts accepting user input. It also initializes user-defined functions.
+pub fn init() {
+    // Hijack KEY_HANDLER with custom key_handler
+    kprintln!("[CONSOLE] Setting handler");
+    let mut handler = KEY_HANDLER.write();
+    *handler = key_handler;
+    // Inialize user functions
+    kprintln!("[CONSOLE] Initalizing custom functions");
+    usr_bin::init();
+    complete::COMPLETERS
+        .write()
+        .insert("unset", |_| ENV.read().keys().cloned().collect());
+}
+/// Handles the keys typed on the terminals, they edit the command line of the active one.
+fn key_handler(c: char, mods: Modifiers) {
+    let vt = vt::active();
+    // The kernel log terminal is output only
+    if vt == LOG_VT {
+        return;
+    }
+    if mods.ctrl && c.is_ascii_alphabetic() {
+        return control(vt, c);
+    }
+    let mut cmdlines = CMD_LINE.write();
+    let editor = &mut cmdlines[vt];
+    if editor.is_empty() {
+        // Output since the prompt moved the cursor, the line starts wherever it is now
+        editor.anchor();
+    }
+    if editor.searching() {
+        // Typing edits the query, escape leaves the search and other keys take the line found
+        match c {
+            '\0' if mods.clear => return editor.search_backspace(),
+            '\u{1b}' => return editor.cancel_search(),
+            c if !c.is_control() => return editor.search_insert(c),
+            _ => editor.accept(),
+        }
+    }
+    match (c, mods.key) {
+        ('\0', _) if mods.clear => editor.backspace(),
+        ('\0', _) if mods.tab => complete(editor),
+        ('\0', Some(KeyCode::ArrowUp)) => editor.up(),
+        ('\0', Some(KeyCode::ArrowDown)) => editor.down(),
+        ('\0', Some(KeyCode::ArrowLeft)) => editor.left(),
+        ('\0', Some(KeyCode::ArrowRight)) => editor.right(),
+        ('\0', Some(KeyCode::Home)) => editor.home(),
+        ('\0', Some(KeyCode::End)) => editor.end(),
+        ('\u{7f}', _) => editor.delete(),
+        ('\n', _) => {
+            editor.finish();
+            let line = editor.text();
+            drop(cmdlines);
+            println!();
+            run(&line);
+            prompt();
+            CMD_LINE.write()[vt].begin();
+        }
+        (c, _) if !c.is_control() => editor.insert(c),
+        _ => {}
+    }
+}
+
+/// Completes the word before the cursor.
+///
+/// A single candidate is completed with a space after it, otherwise the word is extended as far as all
+/// candidates agree. If that does not extend it, the candidates are printed below the command line.
+fn complete(editor: &mut Editor) {
+    let before = editor.before();
+    let (word, candidates) = complete::candidates(&before);
+    let common = complete::common_prefix(&candidates);
+    match candidates.len() {
+        0 => {}
+        1 => {
+            common[word.len()..].chars().for_each(|c| editor.insert(c));
+            editor.insert(' ');
+        }
+        _ if common.len() > word.len() => {
+            common[word.len()..].chars().for_each(|c| editor.insert(c))
+        }
+        _ => {
+            editor.finish();
+            println!();
+            println!("{}", candidates.join("  "));
+            prompt();
+            editor.redisplay();
+        }
+    }
+}
+
+/// Handles a key pressed together with ctrl.
+///
+/// Ctrl+C discards the command line and Ctrl+L clears the screen. Ctrl+A and Ctrl+E move to the start and
+/// the end of the line, Ctrl+U, Ctrl+K and Ctrl+W remove everything before or after the cursor or the word
+/// before it. Ctrl+R searches the history, Ctrl+G leaves the search. Other combinations are ignored.
+fn control(vt: usize, c: char) {
+    let mut cmdlines = CMD_LINE.write();
+    let editor = &mut cmdlines[vt];
+    let c = c.to_ascii_lowercase();
+    if editor.searching() && c != 'r' {
+        match c {
+            'g' => return editor.cancel_search(),
+            _ => editor.accept(),
+        }
+    }
+    match c {
+        'a' => editor.home(),
+        'e' => editor.end(),
+        'k' => editor.kill_end(),
+        'u' => editor.kill_start(),
+        'w' => editor.kill_word(),
+        'r' => editor.search(),
+        'c' => {
+            editor.finish();
+            println!("^C");
+            prompt();
+            editor.begin();
+        }
+        'l' => {
+            clear_all();
+            prompt();
+            editor.redisplay();
+        }
+        _ => {}
+    }
+}
+
+/// Prints the prompt, coloured by the result of the last command.
+fn prompt() {
+    match STATUS.load(Ordering::Relaxed) {
+        0 => COL.lock().set_fg(CMD_OK_COL),
+        _ => COL.lock().set_fg(CMD_ERR_COL),
+    }
+    print!("> "); // print console init
+    COL.lock().set_default();
+}
+
+/// Expands the history references in a command line, adds it to the history and runs it.
+///
+/// An expanded line is printed before it runs, a reference to a line which is not in the history is an error.
+fn run(line: &str) {
+    let expanded = HISTORY.read().expand(line);
+    match expanded {
+        Ok(expanded) => {
+            if let Some(ref line) = expanded {
+                println!("{}", line);
+            }
+            let line = expanded.as_deref().unwrap_or(line);
+            HISTORY.write().push(line);
+            submit(line);
+        }
+        Err(e) => {
+            println!("{}", e);
+            STATUS.store(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Runs a command line, a list of pipelines separated by `;`, `&&` and `||`.
+///
+/// The pipeline after `&&` only runs if the exit status of the last command run is 0, the one after `||`
+/// only if it is not. A pipeline which does not run leaves the exit status as it is, so `a && b || c` runs
+/// `c` if either `a` or `b` fails.
+fn submit(input: &str) {
+    let tokens = match lexer::tokenize(input, variable) {
+        Ok(tokens) => tokens,
+        Err(e) => {
+            println!("{}", e);
+            STATUS.store(USAGE, Ordering::Relaxed);
+            return;
+        }
+    };
+    // check for empty lines
+    if tokens.is_empty() {
+        return;
+    }
+    // The pipelines with the operator before them, made of commands made of words
+    let mut list: Vec<(&Token, Vec<Vec<&str>>)> =
+        Vec::from([(&Token::Seq, Vec::from([Vec::new()]))]);
+    for token in &tokens {
+        let (_, pipeline) = list.last_mut().unwrap();
+        match token {
+            Token::Word(word) => pipeline.last_mut().unwrap().push(word),
+            Token::Pipe => pipeline.push(Vec::new()),
+            op => list.push((op, Vec::from([Vec::new()]))),
+        }
+    }
+    // A ; may end the line
+    if list.len() > 1
+        && list
+            .last()
+            .is_some_and(|(op, pipeline)| **op == Token::Seq && pipeline[0].is_empty())
+    {
+        list.pop();
+    }
+    if list
+        .iter()
+        .any(|(_, pipeline)| pipeline.iter().any(Vec::is_empty))
+    {
+        println!("Missing command");
+        STATUS.store(USAGE, Ordering::Relaxed);
+        return;
+    }
+    for (op, pipeline) in list {
+        let ok = STATUS.load(Ordering::Relaxed) == 0;
+        match op {
+            Token::And if !ok => {}
+            Token::Or if ok => {}
+            _ => run_pipeline(&pipeline),
+        }
+    }
+}
+
+/// Runs the commands of a pipeline, the output of each one is the stdin of the next one. The pipeline stops
+/// at the first command which fails.
+fn run_pipeline(cmds: &[Vec<&str>]) {
+    let mut prev_out: Option<String> = None;
+    for (index, cmdbits) in cmds.iter().enumerate() {
+        // Commands piped into get their stdin even if the previous command had no output
+        let ctx = CmdCtx {
+            args: &cmdbits[1..],
+            stdin: match index {
+                0 => None,
+                _ => Some(prev_out.as_deref().unwrap_or_default()),
+            },
+        };
+        match exec(cmdbits[0], &ctx, &FUNCS.read()) {
+            Ok(out) => prev_out = out,
+            Err(e) => {
+                if !e.msg.is_empty() {
+                    println!("{}", e.msg);
+                }
+                return;
+            }
+        }
+    }
+    print_last_cmd_res(&prev_out)
+}
+
+/// Returns the value of a shell variable, `?` is the exit status of the last command.
+fn variable(name: &str) -> Option<String> {
+    match name {
+        "?" => Some(STATUS.load(Ordering::Relaxed).to_string()),
+        _ => ENV.read().get(name).cloned(),
+    }
+}
+
+// Utils
+#[inline]
+fn print_last_cmd_res(prev_out: &Option<String>) {
+    if let Some(ref out) = prev_out {
+        COL.lock().set_fg(CMD_OUT_COL);
+        println!("<< {}", out);
+        COL.lock().set_default();
+    }
+}
+
+/// Runs a builtin or registered command and stores its exit status.
+#[inline]
+fn exec(cmd: &str, ctx: &CmdCtx, funcs: &HashMap<&str, Cmd>) -> CtxRes {
+    let args = ctx.args;
+    let res = match cmd {
+        "list" => {
+            println!("Available commands:");
+            for func in BUILTINS.iter().chain(funcs.keys()) {
+                println!(" - {}", func);
+            }
+            Ok(None)
+        }
+        "set" => Ok(set(args)?),
+        "unset" => {
+            let mut env = ENV.write();
+            for name in args {
+                env.remove(*name);
+            }
+            Ok(None)
+        }
+        "env" => Ok(Some(
+            ENV.read()
+                .iter()
+                .map(|(name, value)| format!("{}={}", name, value))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        )),
+        "true" => Ok(None),
+        "false" => Err(CmdErr::code(1)),
+        "test" => eval_test(args).map(|_| None),
+        _ => match funcs.get(cmd) {
+            Some(cmd) => cmd.call(ctx),
+            None => Err(CmdErr::new(NOT_FOUND, "Command not found")),
+        },
+    };
+    let status = match res {
+        Ok(_) => 0,
+        Err(ref e) => e.code,
+    };
+    STATUS.store(status, Ordering::Relaxed);
+    res
+}
+
+/// Sets shell variables, given as `NAME=VALUE`. Names consist of letters, digits and `_` and do not start
+/// with a digit.
+fn set(args: CmdArgs) -> CmdRes {
+    if args.is_empty() {
+        return Err("Usage: set <name>=<value>...".to_string());
+    }
+    let mut env = ENV.write();
+    for arg in args {
+        let (name, value) = arg
+            .split_once('=')
+            .filter(|(name, _)| {
+                !name.starts_with(|c: char| c.is_ascii_digit())
+                    && !name.is_empty()
+                    && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            })
+            .ok_or_else(|| format!("Invalid variable assignment {}", arg))?;
+        env.insert(String::from(name), String::from(value));
+    }
+    Ok(None)
+}
+
+/// Evaluates a condition, failing with the exit status 1 if it is false.
+///
+/// The conditions are a string being non-empty (`<s>`, `-n <s>`) or empty (`-z <s>`), two strings being
+/// equal (`=`) or not (`!=`), comparisons of two integers (`-eq`, `-ne`, `-lt`, `-le`, `-gt`, `-ge`) and the
+/// negation of a condition (`! <condition>`). Without arguments the condition is false.
+fn eval_test(args: CmdArgs) -> Result<(), CmdErr> {
+    let int = |arg: &str| {
+        arg.parse::<i64>()
+            .map_err(|_| CmdErr::new(USAGE, format!("test: integer expected, got {}", arg)))
+    };
+    let res = match args {
+        [] => false,
+        [s] => !s.is_empty(),
+        ["!", rest @ ..] => match eval_test(rest) {
+            Ok(()) => false,
+            Err(e) if e.code == 1 => true,
+            Err(e) => return Err(e),
+        },
+        ["-n", s] => !s.is_empty(),
+        ["-z", s] => s.is_empty(),
+        [a, "=", b] => a == b,
+        [a, "!=", b] => a != b,
+        [a, op, b] => {
+            let (a, b) = (int(a)?, int(b)?);
+            match *op {
+                "-eq" => a == b,
+                "-ne" => a != b,
+                "-lt" => a < b,
+                "-le" => a <= b,
+                "-gt" => a > b,
+                "-ge" => a >= b,
+                _ => return Err(CmdErr::new(USAGE, format!("test: unknown operator {}", op))),
+            }
+        }
+        _ => return Err(CmdErr::new(USAGE, "Usage: test [!] <condition>")),
+    };
+    match res {
+        true => Ok(()),
+        false => Err(CmdErr::code(1)),
+    }
+}
+
+// Tests
+#[test_case]
+fn test_cmd() {
+    let stdin = Some("1\n2 3");
+    let args = Cmd::Args(|args| Ok(Some(args.join(","))));
+    test!(
+        "CONSOLE Cmd::Args piped",
+        assert_eq!(
+            args.call(&CmdCtx {
+                args: &["0"],
+                stdin
+            }),
+            Ok(Some(String::from("0,1,2,3")))
+        )
+    );
+    let ctx = Cmd::Ctx(|ctx| Ok(ctx.stdin.map(String::from)));
+    test!(
+        "CONSOLE Cmd::Ctx piped",
+        assert_eq!(
+            ctx.call(&CmdCtx { args: &[], stdin }),
+            Ok(Some(String::from("1\n2 3")))
+        )
+    );
+}
+
+#[test_case]
+fn test_eval_test() {
+    test!(
+        "CONSOLE eval_test()",
+        assert!(eval_test(&["2", "-lt", "10"]).is_ok() && eval_test(&["!", "-z", "a"]).is_ok())
+    );
+    test!(
+        "CONSOLE eval_test() false",
+        assert_eq!(eval_test(&["a", "=", "b"]), Err(CmdErr::code(1)))
+    );
+    test!(
+        "CONSOLE eval_test() usage",
+        assert_eq!(
+            eval_test(&["a", "-lt", "b"]).map_err(|e| e.code),
+            Err(USAGE)
+        )
+    );
+}
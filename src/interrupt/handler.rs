@@ -35,7 +35,7 @@
 // Imports
 use crate::{
     cfg::interrupt::*,
-    eprintln,
+    error,
     interrupt::{
         idt::{irq_index, IRQ_HANDLERS},
         pic::PICS,
@@ -50,7 +50,7 @@ use x86_64::{
 
 /// Handles a page fault exception interrupt.
 pub extern "x86-interrupt" fn page_fault(stack_frame: StackFrame, error_code: PageFaultErrorCode) {
-    eprintln!(
+    error!(
         "EXCEPTION: PAGE FAULT\n-> Accessed Addr: {:?}\n-> Error Code {:?}\nStack Frame-> {:#?}",
         Cr2::read(),
         error_code,
@@ -115,7 +115,7 @@ pub fn clear_irq_mask(irq: u8) {
 
 /// Handles a breakpoint exception interrupt.
 pub extern "x86-interrupt" fn breakpoint(stack_frame: StackFrame) {
-    eprintln!("EXCEPTION: BREAKPOINT\n-> Stack Frame: {:#?}", stack_frame);
+    error!("EXCEPTION: BREAKPOINT\n-> Stack Frame: {:#?}", stack_frame);
 }
 
 /// Handles a double fault exception interrupt.
@@ -33,7 +33,7 @@
 //! This file defines the setup for the IDT in ChadOS.
 
 // Imports
-use crate::{cfg::interrupt::*, interrupt::handler, wprintln};
+use crate::{cfg::interrupt::*, interrupt::handler, warn};
 use lazy_static::lazy_static;
 use spin::RwLock;
 use x86_64::structures::idt::InterruptDescriptorTable;
@@ -86,7 +86,7 @@ lazy_static! {
 
 /// The default IRQ handler, called when an unregistered IRQ is triggered.
 fn default_irq_handler() {
-    wprintln!("Unregistered handler called");
+    warn!("Unregistered handler called");
 }
 
 /// Initializes and loads the IDT, setting up interrupt handlers and their stack indices.
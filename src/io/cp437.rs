@@ -0,0 +1,101 @@
+//     ____ _               _  ___  ____
+//    / ___| |__   __ _  __| |/ _ \/ ___|
+//   | |   | '_ \ / _` |/ _` | | | \___ \
+//   | |___| | | | (_| | (_| | |_| |___) |
+//    \____|_| |_|\__,_|\__,_|\___/|____/
+//    https://github.com/NewDawn0/ChadOS
+//
+//   @Author: NewDawn0
+//   @Contributors: -
+//   @License: MIT
+//
+//   File: src/io/cp437.rs
+//   Desc: Unicode to code page 437 translation
+
+// RustDoc
+//! # Code Page 437 Module
+//!
+//! This module translates Unicode characters into the code page 437 glyphs held by the VGA text buffer,
+//! so that box-drawing, accented and symbol characters can be rendered instead of a placeholder.
+//!
+//! For more information about ChadOS, visit [the ChadOS GitHub repository](https://github.com/NewDawn0/ChadOS).
+//!
+//! ## Author
+//!
+//! - [NewDawn0](https://github.com/NewDawn0)
+//!
+//! ## License
+//!
+//! This code is licensed under the MIT License. See the MIT License section below for details.
+//!
+//! # File: src/io/cp437.rs
+//!
+//! This file defines the Unicode to code page 437 translation table for ChadOS.
+
+// Imports
+#[cfg(test)]
+use crate::test;
+
+/// The glyphs of the code points 0x01 to 0x1f (0x00 is unused).
+const LOW: [char; 32] = [
+    '\0', '☺', '☻', '♥', '♦', '♣', '♠', '•', '◘', '○', '◙', '♂', '♀', '♪', '♫', '☼', //
+    '►', '◄', '↕', '‼', '¶', '§', '▬', '↨', '↑', '↓', '→', '←', '∟', '↔', '▲', '▼',
+];
+
+/// The glyphs of the code points 0x80 to 0xff.
+const HIGH: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å', //
+    'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ', //
+    'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º', '¿', '⌐', '¬', '½', '¼', '¡', '«', '»', //
+    '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕', '╣', '║', '╗', '╝', '╜', '╛', '┐', //
+    '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦', '╠', '═', '╬', '╧', //
+    '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐', '▀', //
+    'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ', 'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩', //
+    '≡', '±', '≥', '≤', '⌠', '⌡', '÷', '≈', '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{a0}',
+];
+
+/// Characters that look the same as an existing glyph.
+const ALIASES: [(char, u8); 5] = [
+    ('β', 0xe1), // Greek beta -> sharp s
+    ('μ', 0xe6), // Greek mu -> micro sign
+    ('∅', 0xed), // Empty set -> phi
+    ('∈', 0xee), // Element of -> epsilon
+    ('⌂', 0x7f), // House
+];
+
+/// The glyph printed for characters without a code page 437 equivalent.
+pub const PLACEHOLDER: u8 = 0xfe;
+
+/// Translates a character into its code page 437 code point.
+///
+/// # Arguments
+///
+/// * `c`: The character to translate.
+///
+/// # Returns
+///
+/// The code point of the glyph, or `None` if code page 437 has no glyph for the character.
+pub fn encode(c: char) -> Option<u8> {
+    match c {
+        '\0' => None,
+        ' '..='~' => Some(c as u8),
+        _ => LOW
+            .iter()
+            .position(|&g| g == c)
+            .or_else(|| HIGH.iter().position(|&g| g == c).map(|i| i + 0x80))
+            .map(|i| i as u8)
+            .or_else(|| ALIASES.iter().find(|(g, _)| *g == c).map(|(_, b)| *b)),
+    }
+}
+
+// Tests
+#[test_case]
+fn test_cp437() {
+    test!("CP437 encode() ascii", assert_eq!(encode('a'), Some(b'a')));
+    test!("CP437 encode() umlaut", assert_eq!(encode('ä'), Some(0x84)));
+    test!("CP437 encode() accent", assert_eq!(encode('é'), Some(0x82)));
+    test!("CP437 encode() box", assert_eq!(encode('╔'), Some(0xc9)));
+    test!("CP437 encode() low", assert_eq!(encode('♥'), Some(0x03)));
+    test!("CP437 encode() alias", assert_eq!(encode('β'), Some(0xe1)));
+    test!("CP437 encode() missing", assert_eq!(encode('€'), None));
+}
@@ -0,0 +1,112 @@
+//     ____ _               _  ___  ____
+//    / ___| |__   __ _  __| |/ _ \/ ___|
+//   | |   | '_ \ / _` |/ _` | | | \___ \
+//   | |___| | | | (_| | (_| | |_| |___) |
+//    \____|_| |_|\__,_|\__,_|\___/|____/
+//    https://github.com/NewDawn0/ChadOS
+//
+//   @Author: NewDawn0
+//   @Contributors: -
+//   @License: MIT
+//
+//   File: src/io/fw_cfg.rs
+//   Desc: QEMU firmware configuration access
+
+// RustDoc
+//! # Firmware Configuration Module
+//!
+//! This module reads files from the QEMU firmware configuration device through its legacy I/O ports. It
+//! allows boot options to be passed on the QEMU command line (`-fw_cfg name=opt/...,string=...`) without
+//! rebuilding the image. On other machines the device is missing and every lookup returns `None`.
+//!
+//! For more information about ChadOS, visit [the ChadOS GitHub repository](https://github.com/NewDawn0/ChadOS).
+//!
+//! ## Author
+//!
+//! - [NewDawn0](https://github.com/NewDawn0)
+//!
+//! ## License
+//!
+//! This code is licensed under the MIT License. See the MIT License section below for details.
+//!
+//! # File: src/io/fw_cfg.rs
+//!
+//! This file defines the QEMU firmware configuration access for ChadOS.
+
+// Imports
+use crate::cfg::fw_cfg::{FW_CFG_DATA_PORT, FW_CFG_SEL_PORT};
+#[cfg(test)]
+use crate::test;
+use x86_64::instructions::{interrupts, port::Port};
+
+/// The selector of the signature, which reads "QEMU" if the device is present.
+const SIGNATURE: u16 = 0x0000;
+/// The selector of the file directory.
+const FILE_DIR: u16 = 0x0019;
+/// The length of a file name in a directory entry.
+const NAME_LEN: usize = 56;
+
+/// Selects an item and reads its first bytes.
+fn read(selector: u16, buf: &mut [u8]) {
+    unsafe {
+        Port::new(FW_CFG_SEL_PORT).write(selector);
+        read_more(buf);
+    }
+}
+
+/// Continues reading the selected item.
+unsafe fn read_more(buf: &mut [u8]) {
+    let mut data = Port::<u8>::new(FW_CFG_DATA_PORT);
+    for b in buf.iter_mut() {
+        *b = data.read();
+    }
+}
+
+/// Returns whether the firmware configuration device is present.
+pub fn available() -> bool {
+    let mut sig = [0; 4];
+    interrupts::without_interrupts(|| read(SIGNATURE, &mut sig));
+    &sig == b"QEMU"
+}
+
+/// Reads a file from the firmware configuration device.
+///
+/// # Arguments
+///
+/// * `name`: The name of the file, e.g. `opt/chados/layout`.
+/// * `buf`: The buffer to read into, longer files are truncated.
+///
+/// # Returns
+///
+/// The number of bytes read, or `None` if the device or the file is missing.
+pub fn read_file(name: &str, buf: &mut [u8]) -> Option<usize> {
+    if !available() {
+        return None;
+    }
+    interrupts::without_interrupts(|| unsafe {
+        let mut count = [0; 4];
+        read(FILE_DIR, &mut count);
+        // Directory entries are big endian: size (u32), selector (u16), reserved (u16), name
+        for _ in 0..u32::from_be_bytes(count) {
+            let mut entry = [0; 8 + NAME_LEN];
+            read_more(&mut entry);
+            let len = entry[8..].iter().position(|&b| b == 0).unwrap_or(NAME_LEN);
+            if &entry[8..8 + len] == name.as_bytes() {
+                let size = u32::from_be_bytes([entry[0], entry[1], entry[2], entry[3]]) as usize;
+                let size = size.min(buf.len());
+                read(u16::from_be_bytes([entry[4], entry[5]]), &mut buf[..size]);
+                return Some(size);
+            }
+        }
+        None
+    })
+}
+
+// Tests
+#[test_case]
+fn test_fw_cfg() {
+    test!(
+        "FW_CFG read_file() missing",
+        assert_eq!(read_file("opt/chados/missing", &mut [0; 4]), None)
+    );
+}
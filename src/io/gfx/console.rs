@@ -0,0 +1,207 @@
+//     ____ _               _  ___  ____
+//    / ___| |__   __ _  __| |/ _ \/ ___|
+//   | |   | '_ \ / _` |/ _` | | | \___ \
+//   | |___| | | | (_| | (_| | |_| |___) |
+//    \____|_| |_|\__,_|\__,_|\___/|____/
+//    https://github.com/NewDawn0/ChadOS
+//
+//   @Author: NewDawn0
+//   @Contributors: -
+//   @License: MIT
+//
+//   File: src/io/gfx/console.rs
+//   Desc: Bitmap font text console
+
+// RustDoc
+//! # Graphics Text Console
+//!
+//! This module renders text onto a pixel `Surface` with the 8x16 text mode font. While a console is attached,
+//! the print macros of `io::vga` are mirrored onto it, so the shell keeps working in graphics modes.
+//! The number of rows and columns follows the resolution of the surface.
+//!
+//! For more information about ChadOS, visit [the ChadOS GitHub repository](https://github.com/NewDawn0/ChadOS).
+//!
+//! ## Author
+//!
+//! - [NewDawn0](https://github.com/NewDawn0)
+//!
+//! ## License
+//!
+//! This code is licensed under the MIT License. See the MIT License section below for details.
+//!
+//! # File: src/io/gfx/console.rs
+//!
+//! This file defines the graphics text console for ChadOS.
+
+// Imports
+use super::{vga::draw_glyph, Surface};
+use crate::{
+    cfg::{
+        gfx::{FONT_HEIGHT, FONT_WIDTH},
+        vga::{BG_COL, FG_COL},
+    },
+    io::{cp437, vga::ColourCode},
+};
+use alloc::boxed::Box;
+use core::fmt;
+use spin::Mutex;
+use x86_64::instructions::interrupts;
+
+// Globals
+/// The graphics text console the print macros are mirrored to, if any.
+pub static CONSOLE: Mutex<Option<Console>> = Mutex::new(None);
+
+/// A text console drawn with a bitmap font.
+pub struct Console {
+    surface: Box<dyn Surface>,
+    rows: usize,
+    cols: usize,
+    row: usize,
+    col: usize,
+    colour: ColourCode,
+    esc: bool,
+}
+
+impl Console {
+    /// Creates a console covering the whole surface, the cursor starts in the top left corner.
+    ///
+    /// # Arguments
+    ///
+    /// * `surface`: The surface to draw on.
+    pub fn new(surface: Box<dyn Surface>) -> Self {
+        Self {
+            rows: surface.height() / FONT_HEIGHT,
+            cols: surface.width() / FONT_WIDTH,
+            surface,
+            row: 0,
+            col: 0,
+            colour: ColourCode::new(FG_COL, BG_COL),
+            esc: false,
+        }
+    }
+
+    /// Sets the colour of the following text.
+    ///
+    /// # Arguments
+    ///
+    /// * `colour`: The foreground and background colour.
+    pub fn set_colour(&mut self, colour: ColourCode) {
+        self.colour = colour;
+    }
+
+    /// Draws a glyph into a cell.
+    fn put(&mut self, row: usize, col: usize, c: u8) {
+        let fg = self.surface.colour(self.colour.fg());
+        let bg = self.surface.colour(self.colour.bg());
+        draw_glyph(
+            self.surface.as_mut(),
+            col * FONT_WIDTH,
+            row * FONT_HEIGHT,
+            c,
+            fg,
+            bg,
+        );
+    }
+
+    /// Writes a glyph at the cursor and advances it, wrapping at the end of the row.
+    fn wg(&mut self, c: u8) {
+        if self.col >= self.cols {
+            self.nl();
+        }
+        self.put(self.row, self.col, c);
+        self.col += 1;
+    }
+
+    /// Moves the cursor to the next row, scrolling the surface on the bottom row.
+    fn nl(&mut self) {
+        self.col = 0;
+        if self.row + 1 < self.rows {
+            self.row += 1;
+            return;
+        }
+        let bg = self.surface.colour(self.colour.bg());
+        self.surface.scroll(FONT_HEIGHT, bg);
+    }
+
+    /// Removes the character before the cursor.
+    pub fn backspace(&mut self) {
+        self.col = self.col.saturating_sub(1);
+        self.put(self.row, self.col, b' ');
+    }
+
+    /// Clears the surface and moves the cursor to the top left corner.
+    pub fn clear(&mut self) {
+        let bg = self.surface.colour(self.colour.bg());
+        self.surface.clear(bg);
+        self.row = 0;
+        self.col = 0;
+    }
+
+    /// Writes a string, escape sequences are skipped.
+    fn ws(&mut self, s: &str) {
+        for c in s.chars() {
+            if self.esc {
+                // Skip everything up to the final byte of the sequence
+                self.esc = !matches!(c, '\x40'..='\x7e') || c == '[';
+                continue;
+            }
+            match c {
+                '\x1b' => self.esc = true,
+                '\n' => self.nl(),
+                '\r' => self.col = 0,
+                '\0' => {}
+                _ => self.wg(cp437::encode(c).unwrap_or(cp437::PLACEHOLDER)),
+            }
+        }
+    }
+}
+
+impl fmt::Write for Console {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.ws(s);
+        Ok(())
+    }
+}
+
+/// Attaches a graphics text console to a surface, the print macros are mirrored to it from now on.
+///
+/// # Arguments
+///
+/// * `surface`: The surface to draw on.
+pub fn attach(surface: Box<dyn Surface>) {
+    *CONSOLE.lock() = Some(Console::new(surface));
+}
+
+/// Detaches the graphics text console.
+pub fn detach() {
+    *CONSOLE.lock() = None;
+}
+
+/// Runs a closure on the surface of the graphics text console if one is attached.
+///
+/// # Arguments
+///
+/// * `f`: The closure drawing on the surface.
+pub fn with_surface(f: impl FnOnce(&mut dyn Surface)) {
+    interrupts::without_interrupts(|| with_console(|console| f(console.surface.as_mut())))
+}
+
+/// Runs a closure on the graphics text console if one is attached.
+///
+/// The closure is skipped if the console is locked by the interrupted code.
+#[doc(hidden)]
+pub fn with_console(f: impl FnOnce(&mut Console)) {
+    if let Some(console) = CONSOLE.try_lock().as_mut().and_then(|c| c.as_mut()) {
+        f(console)
+    }
+}
+
+/// Releases the locks of the graphics text console.
+///
+/// # Safety
+///
+/// Only for the panic handler, the code holding the locks must never run again.
+pub unsafe fn force_unlock() {
+    CONSOLE.force_unlock();
+    super::vga::force_unlock();
+}
@@ -0,0 +1,315 @@
+//     ____ _               _  ___  ____
+//    / ___| |__   __ _  __| |/ _ \/ ___|
+//   | |   | '_ \ / _` |/ _` | | | \___ \
+//   | |___| | | | (_| | (_| | |_| |___) |
+//    \____|_| |_|\__,_|\__,_|\___/|____/
+//    https://github.com/NewDawn0/ChadOS
+//
+//   @Author: NewDawn0
+//   @Contributors: -
+//   @License: MIT
+//
+//   File: src/io/gfx/mod.rs
+//   Desc: Graphics module file
+
+// RustDoc
+//! # Graphics Module
+//!
+//! This module contains the pixel framebuffer drivers of ChadOS and the drawing primitives shared by them.
+//! Every driver implements the `Surface` trait, which provides pixels, lines, rectangles and blits, and can
+//! host the bitmap-font text console from the `console` submodule.
+//!
+//! For more information about ChadOS, visit [the ChadOS GitHub repository](https://github.com/NewDawn0/ChadOS).
+//!
+//! ## Author
+//!
+//! - [NewDawn0](https://github.com/NewDawn0)
+//!
+//! ## License
+//!
+//! This code is licensed under the MIT License. See the MIT License section below for details.
+//!
+//! # File: src/io/gfx/mod.rs
+//!
+//! This file defines the graphics module for ChadOS.
+
+// Modules
+pub mod console;
+pub mod vbe;
+pub mod vga;
+
+// Imports
+#[cfg(test)]
+use crate::test;
+#[cfg(test)]
+use alloc::{vec, vec::Vec};
+
+// Types
+/// A pixel value, its meaning depends on the surface (a palette index or a packed RGB value).
+pub type Pixel = u32;
+
+/// Initializes the graphics drivers.
+///
+/// This function saves the text mode font and palette, so they can be used for rendering text and be
+/// restored when returning to text mode.
+pub fn init() {
+    vga::save_state();
+}
+
+/// A drawable pixel framebuffer.
+///
+/// Drivers only implement the basic pixel access, the other primitives have default implementations built
+/// on top of it. Coordinates outside of the surface are clipped.
+pub trait Surface: Send {
+    /// Returns the width of the surface in pixels.
+    fn width(&self) -> usize;
+
+    /// Returns the height of the surface in pixels.
+    fn height(&self) -> usize;
+
+    /// Sets a single pixel, coordinates outside of the surface are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: The column of the pixel.
+    /// * `y`: The row of the pixel.
+    /// * `c`: The pixel value.
+    fn pixel(&mut self, x: usize, y: usize, c: Pixel);
+
+    /// Translates a text colour (the palette index of a `Colour`, 0-15) into a pixel value.
+    ///
+    /// # Arguments
+    ///
+    /// * `index`: The palette index of the text colour.
+    fn colour(&self, index: u8) -> Pixel;
+
+    /// Moves the contents of the surface up and fills the freed rows at the bottom.
+    ///
+    /// # Arguments
+    ///
+    /// * `dy`: The number of pixel rows to scroll by.
+    /// * `c`: The pixel value of the freed rows.
+    fn scroll(&mut self, dy: usize, c: Pixel);
+
+    /// Draws a line between two points using Bresenham's algorithm, only its visible part is walked.
+    ///
+    /// # Arguments
+    ///
+    /// * `from`: The start point `(x, y)`.
+    /// * `to`: The end point `(x, y)`.
+    /// * `c`: The pixel value.
+    fn line(&mut self, from: (usize, usize), to: (usize, usize), c: Pixel) {
+        let Some((from, to)) = clip_line(from, to, self.width(), self.height()) else {
+            return;
+        };
+        let (mut x, mut y) = (from.0 as isize, from.1 as isize);
+        let (x1, y1) = (to.0 as isize, to.1 as isize);
+        let (dx, dy) = ((x1 - x).abs(), -(y1 - y).abs());
+        let (sx, sy) = ((x1 - x).signum(), (y1 - y).signum());
+        let mut err = dx + dy;
+        loop {
+            self.pixel(x as usize, y as usize, c);
+            if x == x1 && y == y1 {
+                break;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+        }
+    }
+
+    /// Draws the outline of a rectangle.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`, `y`: The top left corner.
+    /// * `w`, `h`: The width and height in pixels.
+    /// * `c`: The pixel value.
+    fn rect(&mut self, x: usize, y: usize, w: usize, h: usize, c: Pixel) {
+        if w == 0 || h == 0 {
+            return;
+        }
+        let (x1, y1) = (x.saturating_add(w - 1), y.saturating_add(h - 1));
+        self.line((x, y), (x1, y), c);
+        self.line((x, y1), (x1, y1), c);
+        self.line((x, y), (x, y1), c);
+        self.line((x1, y), (x1, y1), c);
+    }
+
+    /// Draws a filled rectangle.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`, `y`: The top left corner.
+    /// * `w`, `h`: The width and height in pixels.
+    /// * `c`: The pixel value.
+    fn fill_rect(&mut self, x: usize, y: usize, w: usize, h: usize, c: Pixel) {
+        for row in y..y.saturating_add(h).min(self.height()) {
+            for col in x..x.saturating_add(w).min(self.width()) {
+                self.pixel(col, row, c);
+            }
+        }
+    }
+
+    /// Copies a block of pixels onto the surface.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`, `y`: The top left corner of the destination.
+    /// * `w`: The width of the block, `src` holds the pixels row by row.
+    /// * `src`: The pixels of the block.
+    fn blit(&mut self, x: usize, y: usize, w: usize, src: &[Pixel]) {
+        if w == 0 {
+            return;
+        }
+        for (row, line) in src.chunks(w).enumerate() {
+            for (col, c) in line.iter().enumerate() {
+                self.pixel(x.saturating_add(col), y.saturating_add(row), *c);
+            }
+        }
+    }
+
+    /// Fills the whole surface with one pixel value.
+    ///
+    /// # Arguments
+    ///
+    /// * `c`: The pixel value.
+    fn clear(&mut self, c: Pixel) {
+        self.fill_rect(0, 0, self.width(), self.height(), c);
+    }
+}
+
+/// Clips a line to a surface with the Liang-Barsky algorithm.
+///
+/// The line is walked in fixed point steps of 2^-32 of its length, so the end points of the visible part
+/// are exact for any line shorter than 2^32 pixels.
+///
+/// # Arguments
+///
+/// * `from`: The start point `(x, y)`.
+/// * `to`: The end point `(x, y)`.
+/// * `w`, `h`: The size of the surface.
+///
+/// # Returns
+///
+/// The end points of the visible part of the line, or `None` if no part of it is visible.
+fn clip_line(
+    from: (usize, usize),
+    to: (usize, usize),
+    w: usize,
+    h: usize,
+) -> Option<((usize, usize), (usize, usize))> {
+    const ONE: i128 = 1 << 32;
+    if w == 0 || h == 0 {
+        return None;
+    }
+    let (x0, y0) = (from.0 as i128, from.1 as i128);
+    let (dx, dy) = (to.0 as i128 - x0, to.1 as i128 - y0);
+    // The visible part lies between t0 and t1, in units of ONE
+    let (mut t0, mut t1) = (0, ONE);
+    for (p, q) in [
+        (-dx, x0),
+        (dx, w as i128 - 1 - x0),
+        (-dy, y0),
+        (dy, h as i128 - 1 - y0),
+    ] {
+        match p {
+            0 if q < 0 => return None,
+            0 => {}
+            // The line enters the surface at q / p, rounded up
+            p if p < 0 => t0 = t0.max(-(q * ONE).div_euclid(-p)),
+            // The line leaves the surface at q / p, rounded down
+            p => t1 = t1.min((q * ONE).div_euclid(p)),
+        }
+    }
+    if t0 > t1 {
+        return None;
+    }
+    let at = |t: i128| {
+        let x = x0 + (dx * t + ONE / 2).div_euclid(ONE);
+        let y = y0 + (dy * t + ONE / 2).div_euclid(ONE);
+        (
+            x.clamp(0, w as i128 - 1) as usize,
+            y.clamp(0, h as i128 - 1) as usize,
+        )
+    };
+    Some((at(t0), at(t1)))
+}
+
+// Tests
+/// An in-memory surface for testing the drawing primitives.
+#[cfg(test)]
+struct MemSurface {
+    w: usize,
+    pixels: Vec<Pixel>,
+}
+
+#[cfg(test)]
+impl Surface for MemSurface {
+    fn width(&self) -> usize {
+        self.w
+    }
+    fn height(&self) -> usize {
+        self.pixels.len() / self.w
+    }
+    fn pixel(&mut self, x: usize, y: usize, c: Pixel) {
+        if x < self.width() && y < self.height() {
+            self.pixels[y * self.w + x] = c;
+        }
+    }
+    fn colour(&self, index: u8) -> Pixel {
+        index as Pixel
+    }
+    fn scroll(&mut self, dy: usize, c: Pixel) {
+        let len = self.pixels.len();
+        self.pixels.copy_within(dy * self.w.., 0);
+        self.pixels[len - dy * self.w..].fill(c);
+    }
+}
+
+#[test_case]
+fn test_surface() {
+    let mut s = MemSurface {
+        w: 4,
+        pixels: vec![0; 16],
+    };
+    s.line((0, 0), (3, 3), 1);
+    test!(
+        "GFX Surface.line()",
+        assert_eq!((0..4).map(|i| s.pixels[i * 5]).sum::<Pixel>(), 4)
+    );
+    s.clear(0);
+    s.rect(0, 0, 4, 4, 2);
+    test!(
+        "GFX Surface.rect()",
+        assert_eq!(s.pixels.iter().filter(|&&c| c == 2).count(), 12)
+    );
+    s.fill_rect(2, 2, 8, 8, 3);
+    test!(
+        "GFX Surface.fill_rect() clip",
+        assert_eq!(s.pixels.iter().filter(|&&c| c == 3).count(), 4)
+    );
+    s.blit(0, 0, 2, &[4, 4, 4, 4]);
+    test!(
+        "GFX Surface.blit()",
+        assert_eq!(&s.pixels[..6], &[4, 4, 2, 2, 4, 4])
+    );
+    s.clear(0);
+    s.line((2, 0), (usize::MAX, 0), 5);
+    s.rect(1, 1, usize::MAX, usize::MAX, 5);
+    s.fill_rect(3, 3, usize::MAX, usize::MAX, 5);
+    test!(
+        "GFX Surface clip overflow",
+        assert_eq!(s.pixels.iter().filter(|&&c| c == 5).count(), 8)
+    );
+    test!(
+        "GFX clip_line()",
+        assert_eq!(clip_line((0, 0), (8, 4), 4, 4), Some(((0, 0), (3, 2))))
+    );
+}
@@ -0,0 +1,244 @@
+//     ____ _               _  ___  ____
+//    / ___| |__   __ _  __| |/ _ \/ ___|
+//   | |   | '_ \ / _` |/ _` | | | \___ \
+//   | |___| | | | (_| | (_| | |_| |___) |
+//    \____|_| |_|\__,_|\__,_|\___/|____/
+//    https://github.com/NewDawn0/ChadOS
+//
+//   @Author: NewDawn0
+//   @Contributors: -
+//   @License: MIT
+//
+//   File: src/io/gfx/vbe.rs
+//   Desc: Bochs VBE linear framebuffer driver
+
+// RustDoc
+//! # Bochs VBE Driver
+//!
+//! This module drives the Bochs VBE "dispi" interface of the QEMU/Bochs standard VGA adapter (`-vga std`).
+//! It sets arbitrary resolutions with 32 bits per pixel and maps the linear framebuffer, found in BAR 0 of the
+//! adapter, into the kernel address space. The graphics text console then provides many more rows and columns
+//! than the 80x25 text mode.
+//!
+//! For more information about ChadOS, visit [the ChadOS GitHub repository](https://github.com/NewDawn0/ChadOS).
+//!
+//! ## Author
+//!
+//! - [NewDawn0](https://github.com/NewDawn0)
+//!
+//! ## License
+//!
+//! This code is licensed under the MIT License. See the MIT License section below for details.
+//!
+//! # File: src/io/gfx/vbe.rs
+//!
+//! This file defines the Bochs VBE linear framebuffer driver for ChadOS.
+
+// Imports
+use super::{
+    console,
+    vga::{self, Mode, PALETTE},
+    Pixel, Surface,
+};
+#[cfg(test)]
+use crate::test;
+use crate::{
+    cfg::gfx::{VBE_DATA_PORT, VBE_INDEX_PORT, VBE_LFB_ADDR, VBE_LFB_START, VBE_PCI_ID},
+    io::pci,
+    mem::paging::map_mmio,
+};
+use alloc::boxed::Box;
+#[cfg(test)]
+use alloc::vec;
+use core::{
+    ptr,
+    sync::atomic::{AtomicBool, Ordering},
+};
+use x86_64::{
+    instructions::{interrupts, port::Port},
+    PhysAddr, VirtAddr,
+};
+
+// Dispi registers
+const INDEX_ID: u16 = 0x00;
+const INDEX_XRES: u16 = 0x01;
+const INDEX_YRES: u16 = 0x02;
+const INDEX_BPP: u16 = 0x03;
+const INDEX_ENABLE: u16 = 0x04;
+const INDEX_VIRT_WIDTH: u16 = 0x06;
+
+// Dispi enable flags
+const ENABLED: u16 = 0x01;
+const LFB_ENABLED: u16 = 0x40;
+
+/// The bits per pixel of the framebuffer.
+const BPP: u16 = 32;
+
+// Globals
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Writes a dispi register.
+fn write(index: u16, value: u16) {
+    unsafe {
+        Port::new(VBE_INDEX_PORT).write(index);
+        Port::new(VBE_DATA_PORT).write(value);
+    }
+}
+
+/// Reads a dispi register.
+fn read(index: u16) -> u16 {
+    unsafe {
+        Port::new(VBE_INDEX_PORT).write(index);
+        Port::new(VBE_DATA_PORT).read()
+    }
+}
+
+/// Returns whether the Bochs VBE interface is present.
+pub fn available() -> bool {
+    (0xb0c0..=0xb0c5).contains(&interrupts::without_interrupts(|| read(INDEX_ID)))
+}
+
+/// Returns whether the linear framebuffer is enabled.
+pub fn enabled() -> bool {
+    ACTIVE.load(Ordering::Relaxed)
+}
+
+/// Returns the resolution of the linear framebuffer, or `None` if it is not enabled.
+pub fn resolution() -> Option<(u16, u16)> {
+    enabled().then(|| interrupts::without_interrupts(|| (read(INDEX_XRES), read(INDEX_YRES))))
+}
+
+/// Disables the linear framebuffer and returns the adapter to VGA operation.
+///
+/// The VGA registers still have to be programmed for a mode, see `vga::set_mode`.
+pub(super) fn disable() {
+    if ACTIVE.swap(false, Ordering::Relaxed) {
+        console::detach();
+        write(INDEX_ENABLE, 0);
+    }
+}
+
+/// Switches to a linear framebuffer mode with 32 bits per pixel.
+///
+/// The print macros are rendered by a graphics text console on the framebuffer, use `vga::set_mode` to
+/// return to text mode.
+///
+/// # Arguments
+///
+/// * `width`: The horizontal resolution in pixels.
+/// * `height`: The vertical resolution in pixels.
+///
+/// # Returns
+///
+/// Returns `Ok(())` if the mode is set, or an error message if the adapter or the resolution is not
+/// supported. The display is back in text mode on failure.
+pub fn set_mode(width: u16, height: u16) -> Result<(), &'static str> {
+    if !available() {
+        return Err("Bochs VBE is not available");
+    }
+    vga::set_mode(Mode::Text);
+    interrupts::without_interrupts(|| {
+        console::detach();
+        ACTIVE.store(true, Ordering::Relaxed);
+        write(INDEX_ENABLE, 0);
+        write(INDEX_XRES, width);
+        write(INDEX_YRES, height);
+        write(INDEX_BPP, BPP);
+        write(INDEX_ENABLE, ENABLED | LFB_ENABLED);
+        if (read(INDEX_XRES), read(INDEX_YRES), read(INDEX_BPP)) != (width, height, BPP) {
+            return Err("Resolution is not supported");
+        }
+        let phys = pci::find(VBE_PCI_ID).map_or(VBE_LFB_ADDR, |dev| dev.bar(0));
+        let pitch = read(INDEX_VIRT_WIDTH) as usize;
+        let virt = VirtAddr::new(VBE_LFB_START);
+        let size = (pitch * height as usize * 4) as u64;
+        map_mmio(PhysAddr::new(phys), virt, size).map_err(|_| "Mapping the framebuffer failed")?;
+        let mut fb = LfbFb {
+            mem: virt.as_mut_ptr(),
+            width: width as usize,
+            height: height as usize,
+            pitch,
+        };
+        fb.clear(0);
+        console::attach(Box::new(fb));
+        Ok(())
+    })
+    .inspect_err(|_| vga::set_mode(Mode::Text))
+}
+
+/// A 32 bits per pixel linear framebuffer.
+///
+/// Pixel values are packed `0x00RRGGBB` colours.
+pub struct LfbFb {
+    mem: *mut u32,
+    width: usize,
+    height: usize,
+    pitch: usize,
+}
+
+// The framebuffer is only accessed through the console lock
+unsafe impl Send for LfbFb {}
+
+impl Surface for LfbFb {
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn pixel(&mut self, x: usize, y: usize, c: Pixel) {
+        if x < self.width && y < self.height {
+            unsafe { ptr::write_volatile(self.mem.add(y * self.pitch + x), c) };
+        }
+    }
+
+    fn colour(&self, index: u8) -> Pixel {
+        // Scale the 6-bit DAC values to 8 bits
+        let (r, g, b) = PALETTE[(index & 0x0f) as usize];
+        let scale = |v: u8| (v << 2 | v >> 4) as Pixel;
+        scale(r) << 16 | scale(g) << 8 | scale(b)
+    }
+
+    fn scroll(&mut self, dy: usize, c: Pixel) {
+        let dy = dy.min(self.height);
+        unsafe {
+            ptr::copy(
+                self.mem.add(dy * self.pitch),
+                self.mem,
+                (self.height - dy) * self.pitch,
+            );
+        }
+        self.fill_rect(0, self.height - dy, self.width, dy, c);
+    }
+
+    fn fill_rect(&mut self, x: usize, y: usize, w: usize, h: usize, c: Pixel) {
+        let (x1, y1) = (
+            x.saturating_add(w).min(self.width),
+            y.saturating_add(h).min(self.height),
+        );
+        for row in y..y1 {
+            for col in x..x1 {
+                unsafe { ptr::write_volatile(self.mem.add(row * self.pitch + col), c) };
+            }
+        }
+    }
+}
+
+// Tests
+#[test_case]
+fn test_lfb() {
+    let mut mem = vec![0; 16];
+    let mut fb = LfbFb {
+        mem: mem.as_mut_ptr(),
+        width: 3,
+        height: 3,
+        pitch: 4,
+    };
+    fb.fill_rect(1, 1, usize::MAX, usize::MAX, 5);
+    test!(
+        "GFX LfbFb.fill_rect() clip overflow",
+        assert_eq!(mem, [0, 0, 0, 0, 0, 5, 5, 0, 0, 5, 5, 0, 0, 0, 0, 0])
+    );
+}
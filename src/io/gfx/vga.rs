@@ -0,0 +1,501 @@
+//     ____ _               _  ___  ____
+//    / ___| |__   __ _  __| |/ _ \/ ___|
+//   | |   | '_ \ / _` |/ _` | | | \___ \
+//   | |___| | | | (_| | (_| | |_| |___) |
+//    \____|_| |_|\__,_|\__,_|\___/|____/
+//    https://github.com/NewDawn0/ChadOS
+//
+//   @Author: NewDawn0
+//   @Contributors: -
+//   @License: MIT
+//
+//   File: src/io/gfx/vga.rs
+//   Desc: VGA graphics mode driver
+
+// RustDoc
+//! # VGA Graphics Mode Driver
+//!
+//! This module switches the standard VGA between text mode and the graphics modes 13h (320x200, 256
+//! colours, linear) and 12h (640x480, 16 colours, planar) by programming the VGA registers directly, no
+//! BIOS is involved. The register values are the well known tables of the standard modes.
+//!
+//! The text mode font is saved from plane 2 on boot. It is used by the graphics text console and restored,
+//! together with the palette, when switching back to text mode.
+//!
+//! For more information about ChadOS, visit [the ChadOS GitHub repository](https://github.com/NewDawn0/ChadOS).
+//!
+//! ## Author
+//!
+//! - [NewDawn0](https://github.com/NewDawn0)
+//!
+//! ## License
+//!
+//! This code is licensed under the MIT License. See the MIT License section below for details.
+//!
+//! # File: src/io/gfx/vga.rs
+//!
+//! This file defines the VGA graphics mode driver for ChadOS.
+
+// Imports
+use super::{console, vbe, Pixel, Surface};
+use crate::{
+    cfg::gfx::{FONT_HEIGHT, FONT_WIDTH, VGA_FB_ADDR},
+    io::vga,
+    mem::paging::phys_to_virt,
+};
+use alloc::boxed::Box;
+use core::ptr;
+use spin::Mutex;
+use x86_64::{
+    instructions::{interrupts, port::Port},
+    PhysAddr,
+};
+
+// Register ports (only change if you know what you are doing)
+const AC_PORT: u16 = 0x3c0;
+const MISC_PORT: u16 = 0x3c2;
+const SEQ_PORT: u16 = 0x3c4;
+const DAC_READ_PORT: u16 = 0x3c7;
+const DAC_WRITE_PORT: u16 = 0x3c8;
+const DAC_DATA_PORT: u16 = 0x3c9;
+const GC_PORT: u16 = 0x3ce;
+const CRTC_PORT: u16 = 0x3d4;
+const STATUS_PORT: u16 = 0x3da;
+
+/// The standard 16 colour palette as 6-bit DAC values, in the order of `io::vga::Colour`.
+pub(super) const PALETTE: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (0, 0, 42),
+    (0, 42, 0),
+    (0, 42, 42),
+    (42, 0, 0),
+    (42, 0, 42),
+    (42, 21, 0),
+    (42, 42, 42),
+    (21, 21, 21),
+    (21, 21, 63),
+    (21, 63, 21),
+    (21, 63, 63),
+    (63, 21, 21),
+    (63, 21, 63),
+    (63, 63, 21),
+    (63, 63, 63),
+];
+
+/// The video modes supported by the driver.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// 80x25 text mode.
+    Text,
+    /// 320x200 pixels with 256 colours, one byte per pixel.
+    Mode13h,
+    /// 640x480 pixels with 16 colours, four bit planes.
+    Mode12h,
+}
+
+impl Mode {
+    /// Returns the resolution of the mode in pixels, `(0, 0)` for text mode.
+    pub const fn size(self) -> (usize, usize) {
+        match self {
+            Mode::Text => (0, 0),
+            Mode::Mode13h => (320, 200),
+            Mode::Mode12h => (640, 480),
+        }
+    }
+}
+
+/// Values of the VGA registers describing a video mode.
+struct Regs {
+    misc: u8,
+    seq: [u8; 5],
+    crtc: [u8; 25],
+    gc: [u8; 9],
+    ac: [u8; 21],
+}
+
+/// 80x25 text mode.
+const TEXT_REGS: Regs = Regs {
+    misc: 0x67,
+    seq: [0x03, 0x00, 0x03, 0x00, 0x02],
+    crtc: [
+        0x5f, 0x4f, 0x50, 0x82, 0x55, 0x81, 0xbf, 0x1f, 0x00, 0x4f, 0x0d, 0x0e, 0x00, 0x00, 0x00,
+        0x50, 0x9c, 0x0e, 0x8f, 0x28, 0x1f, 0x96, 0xb9, 0xa3, 0xff,
+    ],
+    gc: [0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0e, 0x00, 0xff],
+    ac: [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x14, 0x07, 0x38, 0x39, 0x3a, 0x3b, 0x3c, 0x3d, 0x3e,
+        0x3f, 0x0c, 0x00, 0x0f, 0x08, 0x00,
+    ],
+};
+
+/// Mode 13h, 320x200x256 with chain-4 addressing.
+const MODE_13H_REGS: Regs = Regs {
+    misc: 0x63,
+    seq: [0x03, 0x01, 0x0f, 0x00, 0x0e],
+    crtc: [
+        0x5f, 0x4f, 0x50, 0x82, 0x54, 0x80, 0xbf, 0x1f, 0x00, 0x41, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x9c, 0x0e, 0x8f, 0x28, 0x40, 0x96, 0xb9, 0xa3, 0xff,
+    ],
+    gc: [0x00, 0x00, 0x00, 0x00, 0x00, 0x40, 0x05, 0x0f, 0xff],
+    ac: [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e,
+        0x0f, 0x41, 0x00, 0x0f, 0x00, 0x00,
+    ],
+};
+
+/// Mode 12h, 640x480x16 planar. The attribute palette maps the colours 1:1 onto the first 16 DAC entries.
+const MODE_12H_REGS: Regs = Regs {
+    misc: 0xe3,
+    seq: [0x03, 0x01, 0x08, 0x00, 0x06],
+    crtc: [
+        0x5f, 0x4f, 0x50, 0x82, 0x54, 0x80, 0x0b, 0x3e, 0x00, 0x40, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0xea, 0x0c, 0xdf, 0x28, 0x00, 0xe7, 0x04, 0xe3, 0xff,
+    ],
+    gc: [0x00, 0x00, 0x00, 0x00, 0x03, 0x00, 0x05, 0x0f, 0xff],
+    ac: [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e,
+        0x0f, 0x01, 0x00, 0x0f, 0x00, 0x00,
+    ],
+};
+
+/// Text mode state saved on boot and the current mode.
+struct State {
+    mode: Mode,
+    font: [[u8; FONT_HEIGHT]; 256],
+    dac: [u8; 768],
+}
+
+// Globals
+static STATE: Mutex<State> = Mutex::new(State {
+    mode: Mode::Text,
+    font: [[0; FONT_HEIGHT]; 256],
+    dac: [0; 768],
+});
+
+/// Writes an indexed register (sequencer, graphics controller or CRT controller).
+fn write_reg(port: u16, index: u8, value: u8) {
+    unsafe {
+        Port::new(port).write(index);
+        Port::new(port + 1).write(value);
+    }
+}
+
+/// Reads an indexed register (sequencer, graphics controller or CRT controller).
+fn read_reg(port: u16, index: u8) -> u8 {
+    unsafe {
+        Port::new(port).write(index);
+        Port::new(port + 1).read()
+    }
+}
+
+/// Programs all registers of a video mode.
+fn write_regs(regs: &Regs) {
+    let mut status: Port<u8> = Port::new(STATUS_PORT);
+    let mut ac: Port<u8> = Port::new(AC_PORT);
+    unsafe {
+        Port::new(MISC_PORT).write(regs.misc);
+    }
+    for (i, v) in regs.seq.iter().enumerate() {
+        write_reg(SEQ_PORT, i as u8, *v);
+    }
+    // Unlock CRTC registers 0-7
+    write_reg(CRTC_PORT, 0x03, read_reg(CRTC_PORT, 0x03) | 0x80);
+    write_reg(CRTC_PORT, 0x11, read_reg(CRTC_PORT, 0x11) & !0x80);
+    for (i, v) in regs.crtc.iter().enumerate() {
+        let v = match i {
+            0x03 => v | 0x80,
+            0x11 => v & !0x80,
+            _ => *v,
+        };
+        write_reg(CRTC_PORT, i as u8, v);
+    }
+    for (i, v) in regs.gc.iter().enumerate() {
+        write_reg(GC_PORT, i as u8, *v);
+    }
+    unsafe {
+        // Reading the status register resets the index/data flip-flop of the attribute controller
+        for (i, v) in regs.ac.iter().enumerate() {
+            status.read();
+            ac.write(i as u8);
+            ac.write(*v);
+        }
+        // Re-enable the display
+        status.read();
+        ac.write(0x20);
+    }
+}
+
+/// Returns the VGA graphics memory window.
+fn vga_mem() -> *mut u8 {
+    phys_to_virt(PhysAddr::new(VGA_FB_ADDR)).as_mut_ptr()
+}
+
+/// Runs a closure with plane 2 (the font plane) mapped linearly at the VGA memory window.
+fn with_font_plane<R>(f: impl FnOnce(*mut u8) -> R) -> R {
+    let (seq2, seq4) = (read_reg(SEQ_PORT, 0x02), read_reg(SEQ_PORT, 0x04));
+    let (gc4, gc5, gc6) = (
+        read_reg(GC_PORT, 0x04),
+        read_reg(GC_PORT, 0x05),
+        read_reg(GC_PORT, 0x06),
+    );
+    write_reg(SEQ_PORT, 0x02, 0x04); // Write plane 2
+    write_reg(SEQ_PORT, 0x04, 0x06); // Sequential addressing
+    write_reg(GC_PORT, 0x04, 0x02); // Read plane 2
+    write_reg(GC_PORT, 0x05, 0x00); // Write mode 0, no odd/even
+    write_reg(GC_PORT, 0x06, 0x04); // Map 64K at 0xa0000
+    let res = f(vga_mem());
+    write_reg(SEQ_PORT, 0x02, seq2);
+    write_reg(SEQ_PORT, 0x04, seq4);
+    write_reg(GC_PORT, 0x04, gc4);
+    write_reg(GC_PORT, 0x05, gc5);
+    write_reg(GC_PORT, 0x06, gc6);
+    res
+}
+
+/// Saves the text mode font and palette, must be called in text mode.
+pub(super) fn save_state() {
+    interrupts::without_interrupts(|| {
+        let mut state = STATE.lock();
+        with_font_plane(|mem| {
+            for (c, glyph) in state.font.iter_mut().enumerate() {
+                for (line, bits) in glyph.iter_mut().enumerate() {
+                    // Every glyph occupies 32 bytes in plane 2
+                    *bits = unsafe { ptr::read_volatile(mem.add(c * 32 + line)) };
+                }
+            }
+        });
+        let mut data: Port<u8> = Port::new(DAC_DATA_PORT);
+        unsafe {
+            Port::new(DAC_READ_PORT).write(0u8);
+            for v in state.dac.iter_mut() {
+                *v = data.read();
+            }
+        }
+    })
+}
+
+/// Restores the text mode font and palette saved by `save_state`.
+fn restore_state(state: &State) {
+    with_font_plane(|mem| {
+        for (c, glyph) in state.font.iter().enumerate() {
+            for (line, bits) in glyph.iter().enumerate() {
+                unsafe { ptr::write_volatile(mem.add(c * 32 + line), *bits) };
+            }
+        }
+    });
+    let mut data: Port<u8> = Port::new(DAC_DATA_PORT);
+    unsafe {
+        Port::new(DAC_WRITE_PORT).write(0u8);
+        for v in state.dac.iter() {
+            data.write(*v);
+        }
+    }
+}
+
+/// Sets a colour of the DAC palette.
+///
+/// # Arguments
+///
+/// * `index`: The palette index.
+/// * `rgb`: The red, green and blue components, 6 bits each (0-63).
+pub fn set_palette(index: u8, rgb: (u8, u8, u8)) {
+    let mut data: Port<u8> = Port::new(DAC_DATA_PORT);
+    unsafe {
+        Port::new(DAC_WRITE_PORT).write(index);
+        data.write(rgb.0 & 0x3f);
+        data.write(rgb.1 & 0x3f);
+        data.write(rgb.2 & 0x3f);
+    }
+}
+
+/// Loads the graphics palette.
+///
+/// Indices 0-15 hold the standard text colours, 16-31 a grey ramp and 32-247 a 6x6x6 colour cube (index
+/// `32 + 36 * r + 6 * g + b`).
+fn load_palette() {
+    for (i, rgb) in PALETTE.iter().enumerate() {
+        set_palette(i as u8, *rgb);
+    }
+    for i in 0..16u8 {
+        set_palette(16 + i, (i * 4 + 3, i * 4 + 3, i * 4 + 3));
+    }
+    for i in 0..216u8 {
+        let level = |v: u8| v * 63 / 5;
+        set_palette(32 + i, (level(i / 36), level(i / 6 % 6), level(i % 6)));
+    }
+}
+
+/// Releases the driver state lock, see `console::force_unlock`.
+pub(super) unsafe fn force_unlock() {
+    STATE.force_unlock();
+}
+
+/// Returns the bitmap of a code page 437 glyph of the text mode font.
+///
+/// # Arguments
+///
+/// * `c`: The code page 437 code point.
+pub fn glyph(c: u8) -> [u8; FONT_HEIGHT] {
+    STATE.lock().font[c as usize]
+}
+
+/// Returns the current video mode.
+pub fn mode() -> Mode {
+    interrupts::without_interrupts(|| STATE.lock().mode)
+}
+
+/// Switches the VGA to another video mode.
+///
+/// In a graphics mode the print macros are rendered by a graphics text console, switching back to text
+/// mode restores the font, the palette and the screen of the active virtual terminal. An enabled VBE
+/// framebuffer is disabled.
+///
+/// # Arguments
+///
+/// * `mode`: The new video mode.
+pub fn set_mode(mode: Mode) {
+    interrupts::without_interrupts(|| {
+        if STATE.lock().mode == mode && !vbe::enabled() {
+            return;
+        }
+        vbe::disable();
+        console::detach();
+        let mut state = STATE.lock();
+        state.mode = mode;
+        match mode {
+            Mode::Text => {
+                write_regs(&TEXT_REGS);
+                restore_state(&state);
+                drop(state);
+                vga::init();
+            }
+            Mode::Mode13h | Mode::Mode12h => {
+                write_regs(match mode {
+                    Mode::Mode13h => &MODE_13H_REGS,
+                    _ => &MODE_12H_REGS,
+                });
+                if mode == Mode::Mode12h {
+                    write_reg(SEQ_PORT, 0x02, 0x0f); // Write all planes
+                    write_reg(GC_PORT, 0x05, 0x02); // Write mode 2, a byte is a colour
+                }
+                load_palette();
+                drop(state);
+                let mut fb = VgaFb::new(mode);
+                fb.clear(0);
+                console::attach(Box::new(fb));
+            }
+        }
+    })
+}
+
+/// A framebuffer of the VGA graphics modes.
+///
+/// Pixel values are palette indices.
+pub struct VgaFb {
+    mode: Mode,
+    mem: *mut u8,
+}
+
+// The framebuffer is only accessed through the console lock
+unsafe impl Send for VgaFb {}
+
+impl VgaFb {
+    /// Creates a framebuffer for a graphics mode, the mode must be set already.
+    fn new(mode: Mode) -> Self {
+        Self {
+            mode,
+            mem: vga_mem(),
+        }
+    }
+
+    /// Returns the number of bytes of one pixel row.
+    fn pitch(&self) -> usize {
+        match self.mode {
+            Mode::Mode12h => self.width() / 8,
+            _ => self.width(),
+        }
+    }
+}
+
+impl Surface for VgaFb {
+    fn width(&self) -> usize {
+        self.mode.size().0
+    }
+
+    fn height(&self) -> usize {
+        self.mode.size().1
+    }
+
+    fn pixel(&mut self, x: usize, y: usize, c: Pixel) {
+        if x >= self.width() || y >= self.height() {
+            return;
+        }
+        match self.mode {
+            Mode::Mode13h => unsafe { ptr::write_volatile(self.mem.add(y * 320 + x), c as u8) },
+            Mode::Mode12h => {
+                let addr = unsafe { self.mem.add(y * self.pitch() + x / 8) };
+                write_reg(GC_PORT, 0x08, 0x80 >> (x & 7)); // Bit mask
+                unsafe {
+                    // Load the latches, so the other pixels of the byte are kept
+                    ptr::read_volatile(addr);
+                    ptr::write_volatile(addr, c as u8);
+                }
+                write_reg(GC_PORT, 0x08, 0xff);
+            }
+            Mode::Text => {}
+        }
+    }
+
+    fn colour(&self, index: u8) -> Pixel {
+        (index & 0x0f) as Pixel
+    }
+
+    fn scroll(&mut self, dy: usize, c: Pixel) {
+        let (pitch, height) = (self.pitch(), self.height());
+        let dy = dy.min(height);
+        match self.mode {
+            Mode::Mode13h => unsafe {
+                ptr::copy(self.mem.add(dy * pitch), self.mem, (height - dy) * pitch);
+                ptr::write_bytes(self.mem.add((height - dy) * pitch), c as u8, dy * pitch);
+            },
+            Mode::Mode12h => unsafe {
+                // Write mode 1 copies the latches, so all four planes are moved at once
+                write_reg(GC_PORT, 0x05, 0x01);
+                for i in 0..(height - dy) * pitch {
+                    let b = ptr::read_volatile(self.mem.add(dy * pitch + i));
+                    ptr::write_volatile(self.mem.add(i), b);
+                }
+                write_reg(GC_PORT, 0x05, 0x02);
+                for i in (height - dy) * pitch..height * pitch {
+                    ptr::write_volatile(self.mem.add(i), c as u8);
+                }
+            },
+            Mode::Text => {}
+        }
+    }
+
+    fn clear(&mut self, c: Pixel) {
+        // In mode 12h write mode 2 sets all eight pixels of a byte
+        unsafe { ptr::write_bytes(self.mem, c as u8, self.pitch() * self.height()) };
+    }
+}
+
+/// Draws a glyph of the text mode font onto a surface.
+///
+/// # Arguments
+///
+/// * `surface`: The surface to draw on.
+/// * `x`, `y`: The top left corner of the glyph.
+/// * `c`: The code page 437 code point.
+/// * `fg`, `bg`: The pixel values of the glyph and its background.
+pub fn draw_glyph(surface: &mut dyn Surface, x: usize, y: usize, c: u8, fg: Pixel, bg: Pixel) {
+    let bits = glyph(c);
+    let pixels: [Pixel; FONT_WIDTH * FONT_HEIGHT] =
+        core::array::from_fn(
+            |i| match bits[i / FONT_WIDTH] & (0x80 >> (i % FONT_WIDTH)) {
+                0 => bg,
+                _ => fg,
+            },
+        );
+    surface.blit(x, y, FONT_WIDTH, &pixels);
+}
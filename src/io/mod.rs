@@ -32,5 +32,13 @@
 //! This file defines the IO module for ChadOS.
 
 // Modules
+pub mod cp437;
+pub mod fw_cfg;
+pub mod gfx;
+pub mod mouse;
+pub mod pci;
+pub mod ps2;
 pub mod serial;
+pub mod status;
 pub mod vga;
+pub mod vt;
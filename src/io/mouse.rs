@@ -0,0 +1,307 @@
+//     ____ _               _  ___  ____
+//    / ___| |__   __ _  __| |/ _ \/ ___|
+//   | |   | '_ \ / _` |/ _` | | | \___ \
+//   | |___| | | | (_| | (_| | |_| |___) |
+//    \____|_| |_|\__,_|\__,_|\___/|____/
+//    https://github.com/NewDawn0/ChadOS
+//
+//   @Author: NewDawn0
+//   @Contributors: -
+//   @License: MIT
+//
+//   File: src/io/mouse.rs
+//   Desc: PS/2 mouse driver
+
+// RustDoc
+//! # Mouse Module
+//!
+//! This module drives a PS/2 mouse on the auxiliary port of the i8042 controller. The packets (3 bytes, or 4
+//! bytes with a scroll wheel) arriving on IRQ 12 are decoded into a position and the button state, which are
+//! delivered to the subscribers as `MouseEvent`s. The pointer is drawn as a block on the VGA text console.
+//!
+//! For more information about ChadOS, visit [the ChadOS GitHub repository](https://github.com/NewDawn0/ChadOS).
+//!
+//! ## Author
+//!
+//! - [NewDawn0](https://github.com/NewDawn0)
+//!
+//! ## License
+//!
+//! This code is licensed under the MIT License. See the MIT License section below for details.
+//!
+//! # File: src/io/mouse.rs
+//!
+//! This file defines the PS/2 mouse driver for ChadOS.
+
+// Imports
+#[cfg(test)]
+use crate::test;
+use crate::{
+    cfg::{
+        gfx::{FONT_HEIGHT, FONT_WIDTH},
+        interrupt::KEYBOARD_PORT,
+        mouse::MOUSE_SUBSCRIBERS,
+        vga::{BUFFER_HEIGHT, BUFFER_WIDTH},
+    },
+    error, info,
+    interrupt::handler::{clear_irq_mask, set_irq_handler},
+    io::{
+        ps2::{self, DEVICE_OK},
+        vga::set_pointer,
+    },
+    warn,
+};
+use spin::{Mutex, RwLock};
+use x86_64::instructions::{interrupts, port::Port};
+
+// Types
+/// A slot of the mouse event subscribers.
+type Subscriber = Option<fn(&MouseEvent)>;
+
+// Globals
+/// The state of the mouse.
+static MOUSE: Mutex<Mouse> = Mutex::new(Mouse::new());
+/// The functions receiving every mouse event, see `subscribe`.
+static SUBSCRIBERS: RwLock<[Subscriber; MOUSE_SUBSCRIBERS]> =
+    RwLock::new([None; MOUSE_SUBSCRIBERS]);
+
+// Mouse commands
+const RESET: u8 = 0xff;
+const SET_DEFAULTS: u8 = 0xf6;
+const SET_SAMPLE_RATE: u8 = 0xf3;
+const GET_ID: u8 = 0xf2;
+const ENABLE_REPORTING: u8 = 0xf4;
+/// The device id of a mouse with a scroll wheel.
+const WHEEL_ID: u8 = 3;
+/// The bit which is always set in the first byte of a packet.
+const SYNC: u8 = 1 << 3;
+
+/// The width of the area the pointer moves in, one unit per pixel of the text console.
+const WIDTH: usize = BUFFER_WIDTH * FONT_WIDTH;
+/// The height of the area the pointer moves in.
+const HEIGHT: usize = BUFFER_HEIGHT * FONT_HEIGHT;
+
+/// The state of the mouse buttons.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Buttons(u8);
+
+impl Buttons {
+    /// Returns whether the left button is pressed.
+    pub fn left(self) -> bool {
+        self.0 & 0b001 != 0
+    }
+
+    /// Returns whether the right button is pressed.
+    pub fn right(self) -> bool {
+        self.0 & 0b010 != 0
+    }
+
+    /// Returns whether the middle button is pressed.
+    pub fn middle(self) -> bool {
+        self.0 & 0b100 != 0
+    }
+}
+
+/// A mouse event, delivered for every packet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MouseEvent {
+    /// The column of the pointer, in pixels of the text console.
+    pub x: usize,
+    /// The row of the pointer, in pixels of the text console (0 is the top).
+    pub y: usize,
+    /// The horizontal movement (positive is right).
+    pub dx: i16,
+    /// The vertical movement (positive is down).
+    pub dy: i16,
+    /// The scroll wheel movement in notches, always 0 without a wheel.
+    pub wheel: i8,
+    /// The buttons held down.
+    pub buttons: Buttons,
+}
+
+/// The packet decoder and the pointer position.
+struct Mouse {
+    packet: [u8; 4],
+    len: usize,
+    size: usize,
+    x: usize,
+    y: usize,
+}
+
+impl Mouse {
+    /// Creates the state for a mouse without a wheel, the pointer starts in the centre.
+    const fn new() -> Self {
+        Self {
+            packet: [0; 4],
+            len: 0,
+            size: 3,
+            x: WIDTH / 2,
+            y: HEIGHT / 2,
+        }
+    }
+
+    /// Adds a byte of a packet.
+    ///
+    /// # Arguments
+    ///
+    /// * `byte`: The byte read from the mouse.
+    ///
+    /// # Returns
+    ///
+    /// The event once the packet is complete.
+    fn feed(&mut self, byte: u8) -> Option<MouseEvent> {
+        // Resynchronize if the first byte was lost
+        if self.len == 0 && byte & SYNC == 0 {
+            return None;
+        }
+        self.packet[self.len] = byte;
+        self.len += 1;
+        if self.len < self.size {
+            return None;
+        }
+        self.len = 0;
+        let [flags, x, y, z] = self.packet;
+        // The movement is a 9-bit two's complement value with the sign in the flags, overflows are dropped
+        let (dx, dy) = match flags & 0xc0 {
+            0 => (
+                x as i16 - ((flags as i16) << 4 & 0x100),
+                -(y as i16 - ((flags as i16) << 3 & 0x100)),
+            ),
+            _ => (0, 0),
+        };
+        self.x = self.x.saturating_add_signed(dx as isize).min(WIDTH - 1);
+        self.y = self.y.saturating_add_signed(dy as isize).min(HEIGHT - 1);
+        Some(MouseEvent {
+            x: self.x,
+            y: self.y,
+            dx,
+            dy,
+            wheel: if self.size == 4 { z as i8 } else { 0 },
+            buttons: Buttons(flags & 0b111),
+        })
+    }
+}
+
+/// A handle to a mouse event subscription, see `unsubscribe`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Subscription(usize);
+
+/// Subscribes a function to all mouse events.
+///
+/// The function is called in interrupt context, so it should return quickly.
+///
+/// # Arguments
+///
+/// * `f`: The function receiving the mouse events.
+///
+/// # Returns
+///
+/// The handle for `unsubscribe`, or an error if there are too many subscribers.
+pub fn subscribe(f: fn(&MouseEvent)) -> Result<Subscription, &'static str> {
+    interrupts::without_interrupts(|| {
+        let mut subscribers = SUBSCRIBERS.write();
+        let index = subscribers
+            .iter()
+            .position(Option::is_none)
+            .ok_or("Too many mouse subscribers")?;
+        subscribers[index] = Some(f);
+        Ok(Subscription(index))
+    })
+}
+
+/// Removes a mouse event subscription.
+///
+/// # Arguments
+///
+/// * `sub`: The handle returned by `subscribe`.
+pub fn unsubscribe(sub: Subscription) {
+    interrupts::without_interrupts(|| SUBSCRIBERS.write()[sub.0] = None)
+}
+
+/// Returns the position of the pointer, in pixels of the text console.
+pub fn position() -> (usize, usize) {
+    interrupts::without_interrupts(|| {
+        let mouse = MOUSE.lock();
+        (mouse.x, mouse.y)
+    })
+}
+
+/// Resets the mouse and enables the scroll wheel if it has one.
+///
+/// # Returns
+///
+/// The size of the packets.
+fn setup() -> Result<usize, ps2::Error> {
+    ps2::send_aux(RESET)?;
+    match ps2::read()? {
+        DEVICE_OK => {}
+        res => return Err(ps2::Error::DeviceTest(res)),
+    }
+    // The device id follows the self-test result
+    ps2::read()?;
+    ps2::send_aux(SET_DEFAULTS)?;
+    // The wheel is enabled by a magic sequence of sample rates
+    for rate in [200, 100, 80] {
+        ps2::send_aux(SET_SAMPLE_RATE)?;
+        ps2::send_aux(rate)?;
+    }
+    ps2::send_aux(GET_ID)?;
+    let size = match ps2::read()? {
+        WHEEL_ID => 4,
+        _ => 3,
+    };
+    ps2::send_aux(ENABLE_REPORTING)?;
+    Ok(size)
+}
+
+/// Initializes the PS/2 mouse, failures are reported through the kernel log.
+pub fn init() {
+    if !ps2::has_aux() {
+        warn!("No PS/2 mouse port");
+        return;
+    }
+    match interrupts::without_interrupts(setup) {
+        Ok(size) => {
+            MOUSE.lock().size = size;
+            set_irq_handler(12, mouse_handler);
+            // IRQ 12 is cascaded through IRQ 2
+            clear_irq_mask(2);
+            info!("PS/2 mouse ready with {}-byte packets", size);
+        }
+        Err(e) => error!("PS/2 mouse initialization failed: {:?}", e),
+    }
+}
+
+/// Mouse interrupt handler function.
+fn mouse_handler() {
+    let byte = unsafe { Port::<u8>::new(KEYBOARD_PORT).read() };
+    let Some(event) = MOUSE.lock().feed(byte) else {
+        return;
+    };
+    set_pointer(Some((event.y / FONT_HEIGHT, event.x / FONT_WIDTH)));
+    // Copy the subscribers, so they may subscribe or unsubscribe themselves
+    let subscribers = *SUBSCRIBERS.read();
+    for f in subscribers.iter().flatten() {
+        f(&event);
+    }
+}
+
+// Tests
+#[test_case]
+fn test_mouse() {
+    let mut mouse = Mouse::new();
+    test!("MOUSE feed() resync", assert_eq!(mouse.feed(0x00), None));
+    // Left button, 5 right and 5 down (the y sign bit is set)
+    mouse.feed(SYNC | 1 << 5 | 0b001);
+    mouse.feed(5);
+    let event = mouse.feed(0xfb).unwrap();
+    test!(
+        "MOUSE feed() movement",
+        assert_eq!((event.dx, event.dy), (5, 5))
+    );
+    test!(
+        "MOUSE feed() position",
+        assert_eq!((event.x, event.y), (WIDTH / 2 + 5, HEIGHT / 2 + 5))
+    );
+    test!("MOUSE feed() buttons", assert!(event.buttons.left()));
+}
@@ -0,0 +1,119 @@
+//     ____ _               _  ___  ____
+//    / ___| |__   __ _  __| |/ _ \/ ___|
+//   | |   | '_ \ / _` |/ _` | | | \___ \
+//   | |___| | | | (_| | (_| | |_| |___) |
+//    \____|_| |_|\__,_|\__,_|\___/|____/
+//    https://github.com/NewDawn0/ChadOS
+//
+//   @Author: NewDawn0
+//   @Contributors: -
+//   @License: MIT
+//
+//   File: src/io/pci.rs
+//   Desc: PCI configuration space access
+
+// RustDoc
+//! # PCI Module
+//!
+//! This module reads the PCI configuration space through the legacy I/O ports (configuration mechanism #1),
+//! which is enough to find devices by their ids and read their base address registers.
+//!
+//! For more information about ChadOS, visit [the ChadOS GitHub repository](https://github.com/NewDawn0/ChadOS).
+//!
+//! ## Author
+//!
+//! - [NewDawn0](https://github.com/NewDawn0)
+//!
+//! ## License
+//!
+//! This code is licensed under the MIT License. See the MIT License section below for details.
+//!
+//! # File: src/io/pci.rs
+//!
+//! This file defines the PCI configuration space access for ChadOS.
+
+// Imports
+use crate::cfg::pci::{CONFIG_ADDR_PORT, CONFIG_DATA_PORT};
+#[cfg(test)]
+use crate::test;
+use x86_64::instructions::{interrupts, port::Port};
+
+/// The location of a function on the PCI bus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Device {
+    pub bus: u8,
+    pub slot: u8,
+    pub func: u8,
+}
+
+impl Device {
+    /// Reads a 32-bit register of the configuration space.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset`: The byte offset of the register, the lower two bits are ignored.
+    pub fn read(&self, offset: u8) -> u32 {
+        let addr = 0x8000_0000
+            | (self.bus as u32) << 16
+            | (self.slot as u32) << 11
+            | (self.func as u32) << 8
+            | (offset & 0xfc) as u32;
+        interrupts::without_interrupts(|| unsafe {
+            Port::new(CONFIG_ADDR_PORT).write(addr);
+            Port::new(CONFIG_DATA_PORT).read()
+        })
+    }
+
+    /// Returns the `(vendor, device)` id pair, the vendor is 0xffff if there is no function.
+    pub fn id(&self) -> (u16, u16) {
+        let id = self.read(0x00);
+        (id as u16, (id >> 16) as u16)
+    }
+
+    /// Returns the address of a memory base address register.
+    ///
+    /// # Arguments
+    ///
+    /// * `bar`: The index of the base address register (0-5).
+    pub fn bar(&self, bar: u8) -> u64 {
+        (self.read(0x10 + bar * 4) & 0xffff_fff0) as u64
+    }
+}
+
+/// Searches the PCI bus for a device.
+///
+/// # Arguments
+///
+/// * `id`: The `(vendor, device)` id pair to search for.
+///
+/// # Returns
+///
+/// The first function matching the ids, or `None` if there is none.
+pub fn find(id: (u16, u16)) -> Option<Device> {
+    for bus in 0..=255u8 {
+        for slot in 0..32u8 {
+            // Skip empty slots, a device always implements function 0
+            if (Device { bus, slot, func: 0 }).id().0 == 0xffff {
+                continue;
+            }
+            for func in 0..8u8 {
+                let dev = Device { bus, slot, func };
+                match dev.id() {
+                    (0xffff, _) => {}
+                    dev_id if dev_id == id => return Some(dev),
+                    _ => {}
+                }
+            }
+        }
+    }
+    None
+}
+
+// Tests
+#[test_case]
+fn test_pci() {
+    test!(
+        "PCI find() missing",
+        assert_eq!(find((0xffff, 0xffff)), None)
+    );
+}
@@ -0,0 +1,277 @@
+//     ____ _               _  ___  ____
+//    / ___| |__   __ _  __| |/ _ \/ ___|
+//   | |   | '_ \ / _` |/ _` | | | \___ \
+//   | |___| | | | (_| | (_| | |_| |___) |
+//    \____|_| |_|\__,_|\__,_|\___/|____/
+//    https://github.com/NewDawn0/ChadOS
+//
+//   @Author: NewDawn0
+//   @Contributors: -
+//   @License: MIT
+//
+//   File: src/io/ps2.rs
+//   Desc: PS/2 (i8042) controller driver
+
+// RustDoc
+//! # PS/2 Controller Module
+//!
+//! This module drives the i8042 PS/2 controller. On boot the controller is brought into a known state: both
+//! ports are disabled, stale output is flushed, the controller and the keyboard port run their self-tests,
+//! the configuration byte is rewritten and the keyboard is reset and switched to scancode set 2 (translated to
+//! set 1 by the controller). The auxiliary port is enabled for the mouse if it passes its self-test. Failures
+//! are reported through the kernel log.
+//!
+//! For more information about ChadOS, visit [the ChadOS GitHub repository](https://github.com/NewDawn0/ChadOS).
+//!
+//! ## Author
+//!
+//! - [NewDawn0](https://github.com/NewDawn0)
+//!
+//! ## License
+//!
+//! This code is licensed under the MIT License. See the MIT License section below for details.
+//!
+//! # File: src/io/ps2.rs
+//!
+//! This file defines the PS/2 controller driver for ChadOS.
+
+// Imports
+#[cfg(test)]
+use crate::test;
+use crate::{
+    cfg::interrupt::{KEYBOARD_PORT, KEYBOARD_STATUS_PORT},
+    error, info, warn,
+};
+use core::sync::atomic::{AtomicBool, Ordering};
+use x86_64::instructions::{interrupts, port::Port};
+
+// Globals
+/// Whether the auxiliary (mouse) port is available.
+static AUX: AtomicBool = AtomicBool::new(false);
+
+/// Status bit set while the output buffer holds a byte for the CPU.
+const OUTPUT_FULL: u8 = 1 << 0;
+/// Status bit set while the controller has not consumed the last written byte.
+const INPUT_FULL: u8 = 1 << 1;
+
+// Controller commands
+const READ_CONFIG: u8 = 0x20;
+const WRITE_CONFIG: u8 = 0x60;
+const DISABLE_PORT2: u8 = 0xa7;
+const ENABLE_PORT2: u8 = 0xa8;
+const TEST_PORT2: u8 = 0xa9;
+const TEST_CONTROLLER: u8 = 0xaa;
+const TEST_PORT1: u8 = 0xab;
+const DISABLE_PORT1: u8 = 0xad;
+const ENABLE_PORT1: u8 = 0xae;
+const WRITE_PORT2: u8 = 0xd4;
+
+// Configuration byte bits
+const CFG_PORT1_IRQ: u8 = 1 << 0;
+const CFG_PORT2_IRQ: u8 = 1 << 1;
+const CFG_PORT1_CLOCK_OFF: u8 = 1 << 4;
+const CFG_PORT2_CLOCK_OFF: u8 = 1 << 5;
+const CFG_TRANSLATE: u8 = 1 << 6;
+
+// Device commands
+const RESET: u8 = 0xff;
+const SET_SCANCODE_SET: u8 = 0xf0;
+const ENABLE_SCANNING: u8 = 0xf4;
+
+// Responses
+/// Device response acknowledging a command.
+pub const ACK: u8 = 0xfa;
+/// Device response requesting the last byte again.
+pub const RESEND: u8 = 0xfe;
+const CONTROLLER_OK: u8 = 0x55;
+const PORT_OK: u8 = 0x00;
+/// Device response to a reset if its self-test passed.
+pub const DEVICE_OK: u8 = 0xaa;
+
+/// The scancode set requested from the keyboard, the controller translates it to set 1.
+const SCANCODE_SET: u8 = 2;
+/// The number of status polls before the controller is considered unresponsive.
+const TIMEOUT: usize = 1_000_000;
+/// The number of times a byte is sent again when the device requests it.
+const RETRIES: usize = 3;
+
+/// Errors of the PS/2 controller and devices, carrying the unexpected response if there is one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The controller did not accept or answer a byte in time.
+    Timeout,
+    /// The controller self-test failed.
+    ControllerTest(u8),
+    /// A port self-test failed.
+    PortTest(u8),
+    /// The device did not acknowledge a byte.
+    NoAck(u8),
+    /// The device self-test after the reset failed.
+    DeviceTest(u8),
+}
+
+/// Waits until a status bit has the expected value.
+fn wait(bit: u8, set: bool) -> Result<(), Error> {
+    let mut status = Port::<u8>::new(KEYBOARD_STATUS_PORT);
+    match (0..TIMEOUT).any(|_| (unsafe { status.read() } & bit != 0) == set) {
+        true => Ok(()),
+        false => Err(Error::Timeout),
+    }
+}
+
+/// Reads a byte from the output buffer, waiting for it to arrive.
+pub fn read() -> Result<u8, Error> {
+    wait(OUTPUT_FULL, true)?;
+    Ok(unsafe { Port::new(KEYBOARD_PORT).read() })
+}
+
+/// Writes a byte to a controller port once the input buffer is empty.
+fn write(port: u16, byte: u8) -> Result<(), Error> {
+    wait(INPUT_FULL, false)?;
+    unsafe { Port::new(port).write(byte) };
+    Ok(())
+}
+
+/// Sends a command to the controller.
+fn command(cmd: u8) -> Result<(), Error> {
+    write(KEYBOARD_STATUS_PORT, cmd)
+}
+
+/// Sends a command to the controller and reads its response.
+fn query(cmd: u8) -> Result<u8, Error> {
+    command(cmd)?;
+    read()
+}
+
+/// Writes the configuration byte of the controller.
+fn write_config(config: u8) -> Result<(), Error> {
+    command(WRITE_CONFIG)?;
+    write(KEYBOARD_PORT, config)
+}
+
+/// Discards everything in the output buffer.
+fn flush() {
+    let mut status = Port::<u8>::new(KEYBOARD_STATUS_PORT);
+    let mut data = Port::<u8>::new(KEYBOARD_PORT);
+    for _ in 0..TIMEOUT {
+        if unsafe { status.read() } & OUTPUT_FULL == 0 {
+            break;
+        }
+        unsafe { data.read() };
+    }
+}
+
+/// Sends a byte to the keyboard and waits for the acknowledgement, resending it if requested.
+///
+/// Interrupts are disabled while waiting, so the keyboard interrupt handler does not take the response.
+///
+/// # Arguments
+///
+/// * `byte`: The command or data byte.
+pub fn send(byte: u8) -> Result<(), Error> {
+    transmit(false, byte)
+}
+
+/// Sends a byte to the device on the auxiliary (mouse) port, see `send`.
+///
+/// # Arguments
+///
+/// * `byte`: The command or data byte.
+pub fn send_aux(byte: u8) -> Result<(), Error> {
+    transmit(true, byte)
+}
+
+/// Returns whether the auxiliary (mouse) port is available.
+pub fn has_aux() -> bool {
+    AUX.load(Ordering::Relaxed)
+}
+
+/// Sends a byte to a device and waits for the acknowledgement.
+fn transmit(aux: bool, byte: u8) -> Result<(), Error> {
+    interrupts::without_interrupts(|| {
+        for _ in 0..RETRIES {
+            if aux {
+                command(WRITE_PORT2)?;
+            }
+            write(KEYBOARD_PORT, byte)?;
+            match read()? {
+                ACK => return Ok(()),
+                RESEND => continue,
+                res => return Err(Error::NoAck(res)),
+            }
+        }
+        Err(Error::NoAck(RESEND))
+    })
+}
+
+/// Checks whether the controller has a second (mouse) port, the port is disabled afterwards.
+fn dual_channel() -> Result<bool, Error> {
+    command(ENABLE_PORT2)?;
+    let dual = query(READ_CONFIG)? & CFG_PORT2_CLOCK_OFF == 0;
+    command(DISABLE_PORT2)?;
+    Ok(dual)
+}
+
+/// Brings the controller and the keyboard into a known state.
+///
+/// # Returns
+///
+/// The number of working ports.
+fn setup() -> Result<usize, Error> {
+    command(DISABLE_PORT1)?;
+    command(DISABLE_PORT2)?;
+    flush();
+    // Disable the interrupts until the devices are ready
+    let config = query(READ_CONFIG)? & !(CFG_PORT1_IRQ | CFG_PORT2_IRQ);
+    write_config(config)?;
+    match query(TEST_CONTROLLER)? {
+        CONTROLLER_OK => {}
+        res => return Err(Error::ControllerTest(res)),
+    }
+    // The self-test may reset the controller
+    write_config(config)?;
+    let mut aux = false;
+    if dual_channel()? {
+        match query(TEST_PORT2)? {
+            PORT_OK => aux = true,
+            res => warn!("PS/2 port 2 self-test failed: {:#x}", res),
+        }
+    }
+    match query(TEST_PORT1)? {
+        PORT_OK => {}
+        res => return Err(Error::PortTest(res)),
+    }
+    command(ENABLE_PORT1)?;
+    // The keyboard acknowledges the reset and then reports its self-test result
+    send(RESET)?;
+    match read()? {
+        DEVICE_OK => {}
+        res => return Err(Error::DeviceTest(res)),
+    }
+    send(SET_SCANCODE_SET)?;
+    send(SCANCODE_SET)?;
+    send(ENABLE_SCANNING)?;
+    // The clock bits still reflect the disabled ports
+    let mut config = (config | CFG_PORT1_IRQ | CFG_TRANSLATE) & !CFG_PORT1_CLOCK_OFF;
+    if aux {
+        command(ENABLE_PORT2)?;
+        config = (config | CFG_PORT2_IRQ) & !CFG_PORT2_CLOCK_OFF;
+    }
+    write_config(config)?;
+    AUX.store(aux, Ordering::Relaxed);
+    Ok(1 + aux as usize)
+}
+
+/// Initializes the PS/2 controller and the keyboard, failures are reported through the kernel log.
+pub fn init() {
+    interrupts::without_interrupts(|| match setup() {
+        Ok(ports) => info!("PS/2 controller ready with {} port(s)", ports),
+        Err(e) => error!("PS/2 controller initialization failed: {:?}", e),
+    })
+}
+
+// Tests
+#[test_case]
+fn test_ps2() {
+    test!("PS/2 send()", assert_eq!(send(ENABLE_SCANNING), Ok(())));
+}
@@ -0,0 +1,97 @@
+//     ____ _               _  ___  ____
+//    / ___| |__   __ _  __| |/ _ \/ ___|
+//   | |   | '_ \ / _` |/ _` | | | \___ \
+//   | |___| | | | (_| | (_| | |_| |___) |
+//    \____|_| |_|\__,_|\__,_|\___/|____/
+//    https://github.com/NewDawn0/ChadOS
+//
+//   @Author: NewDawn0
+//   @Contributors: -
+//   @License: MIT
+//
+//   File: src/io/status.rs
+//   Desc: Status bar
+
+// RustDoc
+//! # Status Bar Module
+//!
+//! This module keeps the status bar in the top row of every virtual terminal up to date. It shows the active
+//! terminal, the uptime, the heap usage, the number of tasks and the keyboard layout, and is refreshed once
+//! a second by an executor task.
+//!
+//! For more information about ChadOS, visit [the ChadOS GitHub repository](https://github.com/NewDawn0/ChadOS).
+//!
+//! ## Author
+//!
+//! - [NewDawn0](https://github.com/NewDawn0)
+//!
+//! ## License
+//!
+//! This code is licensed under the MIT License. See the MIT License section below for details.
+//!
+//! # File: src/io/status.rs
+//!
+//! This file defines the status bar for ChadOS.
+
+// Imports
+#[cfg(test)]
+use crate::test;
+use crate::{
+    cfg::{
+        time::PIT_HZ,
+        vga::{STATUS_BG, STATUS_FG},
+    },
+    io::{vga::ColourCode, vt::VTS},
+    keys,
+    mem::alloc::init::usage,
+    sched::task_count,
+    time::{delay, Uptime},
+};
+use alloc::{format, string::String};
+use x86_64::instructions::interrupts;
+
+/// Formats the status bar text.
+///
+/// # Arguments
+///
+/// * `vt`: The index of the active virtual terminal.
+fn text(vt: usize) -> String {
+    let (used, free) = usage();
+    format!(
+        " VT{} | up {} | heap {}K used {}K free | tasks {} | {}",
+        vt + 1,
+        Uptime::string_fmt(),
+        used / 1024,
+        free / 1024,
+        task_count(),
+        keys::layout(),
+    )
+}
+
+/// Redraws the status bar of all virtual terminals.
+pub fn refresh() {
+    let colour = ColourCode::new(STATUS_FG, STATUS_BG);
+    let vt = interrupts::without_interrupts(|| VTS.lock().index());
+    let text = text(vt);
+    interrupts::without_interrupts(|| {
+        for writer in VTS.lock().iter_mut() {
+            writer.status(&text, colour);
+        }
+    });
+}
+
+/// Refreshes the status bar once a second, spawn it on the executor.
+pub async fn run() {
+    loop {
+        refresh();
+        delay(PIT_HZ as usize).await;
+    }
+}
+
+// Tests
+#[test_case]
+fn test_status() {
+    let text = text(0);
+    test!("STATUS text() vt", assert!(text.starts_with(" VT1 |")));
+    test!("STATUS text() fits", assert!(text.len() <= 80));
+}
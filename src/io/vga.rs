@@ -33,13 +33,26 @@
 //! This file defines the VGA printing implementation for ChadOS.
 
 // Imports
-use crate::cfg::vga::*;
 #[cfg(test)]
 use crate::test;
-use core::fmt;
-use lazy_static::lazy_static;
-use spin::Mutex;
+use crate::{
+    cfg::vga::*,
+    io::{
+        cp437,
+        gfx::console::{force_unlock as force_unlock_console, with_console},
+        vt::{Vts, VTS},
+    },
+};
+use alloc::{boxed::Box, vec};
+use core::{
+    fmt,
+    mem::ManuallyDrop,
+    ops::{Deref, DerefMut},
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+use spin::MutexGuard;
 use volatile::Volatile;
+use x86_64::instructions::{interrupts, port::Port};
 
 // Macros
 
@@ -68,18 +81,13 @@ macro_rules! println {
     ($($arg:tt)*) => ($crate::print!("{}\n", format_args!($($arg)*)));
 }
 
-/// Prints a formatted message to the VGA buffer with the kernel label.
+/// Logs a formatted kernel message.
 ///
-/// This macro is similar to `println!` but prepends a "\[KERNEL\]" label to the output.
+/// This macro is an alias of `info!`, the record is kept in the kernel log and printed with a "\[KERNEL\]"
+/// label to the virtual terminal `LOG_VT`, regardless of which terminal is active.
 #[macro_export]
 macro_rules! kprintln {
-    ($($arg:tt)*) => {
-        $crate::io::vga::COL.lock().set_fg($crate::cfg::vga::KERNELP_COLOUR);
-        $crate::print!("[KERNEL]");
-        $crate::io::vga::COL.lock().set_fg($crate::cfg::vga::KERNELM_COLOUR);
-        $crate::println!(" {}", format_args!($($arg)*));
-        $crate::io::vga::COL.lock().set_default();
-    };
+    ($($arg:tt)*) => ($crate::info!($($arg)*));
 }
 
 /// Prints a warning message to the VGA buffer.
@@ -87,11 +95,12 @@ macro_rules! kprintln {
 /// This macro is similar to `println!` but prepends a "\[WARN\]" label to the output.
 #[macro_export]
 macro_rules! wprintln {
-    ($($arg:tt)*) => {
-        $crate::io::vga::COL.lock().set_fg($crate::cfg::vga::WARN_COLOUR);
-        $crate::println!("[WARN] {}", format_args!($($arg)*));
-        $crate::io::vga::COL.lock().set_default();
-    };
+    ($($arg:tt)*) => ($crate::io::vga::_lprint(
+        None,
+        "[WARN]",
+        ($crate::cfg::vga::WARN_COLOUR, $crate::cfg::vga::WARN_COLOUR),
+        format_args!($($arg)*),
+    ));
 }
 
 /// Prints an error message to the VGA buffer.
@@ -99,25 +108,21 @@ macro_rules! wprintln {
 /// This macro is similar to `println!` but prepends an "\[ERROR\]" label to the output.
 #[macro_export]
 macro_rules! eprintln {
-    ($($arg:tt)*) => {
-        $crate::io::vga::COL.lock().set_fg($crate::cfg::vga::ERRP_COLOUR);
-        $crate::print!("[ERROR]");
-        $crate::io::vga::COL.lock().set_fg($crate::cfg::vga::ERRM_COLOUR);
-        $crate::println!(" {}", format_args!($($arg)*));
-        $crate::io::vga::COL.lock().set_default();
-    };
+    ($($arg:tt)*) => ($crate::io::vga::_lprint(
+        None,
+        "[ERROR]",
+        ($crate::cfg::vga::ERRP_COLOUR, $crate::cfg::vga::ERRM_COLOUR),
+        format_args!($($arg)*),
+    ));
 }
 
 // Globals
-/// The global static Col instance colour of the text
-pub static COL: Mutex<Col> = Mutex::new(Col::new(FG_COL, BG_COL));
-lazy_static! {
-    /// The global static writer instance for the VGA buffer.
-    pub static ref WRITER: Mutex<Writer> = Mutex::new(Writer {
-        column_position: 0,
-        buffer: unsafe { &mut *(BUF_ADDR as *mut Buffer) },
-    });
-}
+/// The global static colour of the text on the active virtual terminal.
+pub static COL: ActiveCol = ActiveCol;
+/// The cell of the mouse pointer (`row * BUFFER_WIDTH + column`), see `set_pointer`.
+static POINTER: AtomicUsize = AtomicUsize::new(NO_POINTER);
+/// The value of `POINTER` while the pointer is hidden.
+const NO_POINTER: usize = usize::MAX;
 
 /// The standard colour palette in VGA text mode.
 #[allow(dead_code)]
@@ -142,6 +147,26 @@ pub enum Colour {
     White = 15,
 }
 
+/// Maps the ANSI colour indices (normal 0-7, bright 8-15) onto the VGA palette.
+const ANSI_COLOURS: [Colour; 16] = [
+    Colour::Black,
+    Colour::Red,
+    Colour::Green,
+    Colour::Brown,
+    Colour::Blue,
+    Colour::Magenta,
+    Colour::Cyan,
+    Colour::LightGrey,
+    Colour::DarkGrey,
+    Colour::LightRed,
+    Colour::LightGreen,
+    Colour::Yellow,
+    Colour::LightBlue,
+    Colour::Pink,
+    Colour::LightCyan,
+    Colour::White,
+];
+
 /// Represents a VGA text color configuration.
 pub struct Col(Colour, Colour);
 
@@ -203,6 +228,55 @@ impl Col {
     }
 }
 
+/// Handle to the colour state of the active virtual terminal.
+///
+/// Every virtual terminal has its own `Col`, this handle always refers to the one of the terminal that
+/// is currently shown. The colour lives behind the same lock as the writer.
+pub struct ActiveCol;
+
+impl ActiveCol {
+    /// Locks the virtual terminals and returns the colour of the active one.
+    ///
+    /// Interrupts are disabled while the guard is alive, so an interrupt handler can not deadlock on it.
+    pub fn lock(&self) -> ColGuard<'_> {
+        let enabled = interrupts::are_enabled();
+        interrupts::disable();
+        ColGuard {
+            guard: ManuallyDrop::new(VTS.lock()),
+            enabled,
+        }
+    }
+}
+
+/// Guard giving access to the colour of the active virtual terminal, see `ActiveCol`.
+pub struct ColGuard<'a> {
+    guard: ManuallyDrop<MutexGuard<'a, Vts>>,
+    enabled: bool,
+}
+
+impl Deref for ColGuard<'_> {
+    type Target = Col;
+    fn deref(&self) -> &Col {
+        &self.guard.active_ref().col
+    }
+}
+
+impl DerefMut for ColGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Col {
+        &mut self.guard.active().col
+    }
+}
+
+impl Drop for ColGuard<'_> {
+    fn drop(&mut self) {
+        // Release the lock before interrupts are enabled again
+        unsafe { ManuallyDrop::drop(&mut self.guard) };
+        if self.enabled {
+            interrupts::enable();
+        }
+    }
+}
+
 /// VGA text mode colour code.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(transparent)]
@@ -211,6 +285,21 @@ impl ColourCode {
     pub const fn new(fg: Colour, bg: Colour) -> Self {
         Self((bg as u8) << 4 | (fg as u8))
     }
+
+    /// Returns the palette index (0-15) of the foreground colour.
+    pub const fn fg(self) -> u8 {
+        self.0 & 0x0f
+    }
+
+    /// Returns the palette index (0-15) of the background colour.
+    pub const fn bg(self) -> u8 {
+        self.0 >> 4
+    }
+
+    /// Returns the colour with foreground and background swapped.
+    pub const fn inverted(self) -> Self {
+        Self(self.0.rotate_left(4))
+    }
 }
 
 /// Represents a single character on the VGA screen.
@@ -227,12 +316,6 @@ impl ScreenChar {
             colour_code,
         }
     }
-    pub fn blank() -> Self {
-        Self {
-            ascii_char: ASCII_BLANK,
-            colour_code: COL.lock().get(),
-        }
-    }
 }
 
 /// Represents the VGA text buffer.
@@ -241,13 +324,199 @@ struct Buffer {
     chars: [[Volatile<ScreenChar>; BUFFER_WIDTH]; BUFFER_HEIGHT],
 }
 
+/// Returns the VGA text buffer.
+///
+/// Only the visible writer may write to it.
+fn buffer() -> &'static mut Buffer {
+    unsafe { &mut *(BUF_ADDR as *mut Buffer) }
+}
+
+/// A single screen row as it is stored in the VGA text buffer.
+type Row = [ScreenChar; BUFFER_WIDTH];
+
+/// Ring buffer of rows that were scrolled off the top of the screen.
+///
+/// The rows are allocated once by `alloc`, so adding a row never allocates and is safe while printing from
+/// interrupt handlers, which may interrupt the allocator. `head` is the index of the oldest row and `len`
+/// the number of rows kept. `offset` is the number of rows the viewport is scrolled back into the history,
+/// 0 shows the live screen.
+struct Scrollback {
+    rows: Option<Box<[Row]>>,
+    head: usize,
+    len: usize,
+    offset: usize,
+}
+
+impl Scrollback {
+    /// Creates an empty scrollback history. Does not allocate.
+    const fn new() -> Self {
+        Self {
+            rows: None,
+            head: 0,
+            len: 0,
+            offset: 0,
+        }
+    }
+
+    /// Allocates the rows of the history. Must be called after the heap is initialized.
+    fn alloc(&mut self) {
+        let blank = ScreenChar::new(ASCII_BLANK, ColourCode::new(FG_COL, BG_COL));
+        self.rows = Some(vec![[blank; BUFFER_WIDTH]; SCROLLBACK_LINES].into_boxed_slice());
+    }
+
+    /// Returns the number of rows in the history.
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Appends a row to the history, overwriting the oldest one once `SCROLLBACK_LINES` is reached.
+    ///
+    /// Rows are silently discarded while the history is not allocated yet.
+    fn push(&mut self, row: &Row) {
+        let Some(rows) = self.rows.as_mut() else {
+            return;
+        };
+        if self.len < rows.len() {
+            rows[(self.head + self.len) % rows.len()] = *row;
+            self.len += 1;
+        } else {
+            rows[self.head] = *row;
+            self.head = (self.head + 1) % rows.len();
+        }
+    }
+
+    /// Returns the history row at `index`, 0 being the oldest row.
+    fn row(&self, index: usize) -> Row {
+        let rows = self.rows.as_deref().unwrap_or_default();
+        rows[(self.head + index) % rows.len()]
+    }
+}
+
+/// State of the ANSI/VT100 escape sequence parser.
+#[derive(Clone, Copy)]
+enum Esc {
+    /// Plain text.
+    None,
+    /// An `ESC` byte was received.
+    Escape,
+    /// Inside a control sequence (`ESC [`), collecting numeric parameters.
+    Csi {
+        params: [u16; ANSI_MAX_PARAMS],
+        len: usize,
+        private: bool,
+    },
+}
+
 /// VGA text buffer writer.
+///
+/// Text is inserted at the cursor (`row_position`, `column_position`). Once the cursor reaches the
+/// bottom row, every new line scrolls the screen up by one row. The top `STATUS_ROWS` rows hold the
+/// status bar and are outside of the scrolling region.
+///
+/// Every writer renders into its own off-screen copy of the screen and has its own colour. The output is
+/// only mirrored into the VGA text buffer while the writer is `visible`, which allows one writer per
+/// virtual terminal.
 pub struct Writer {
+    row_position: usize,
     column_position: usize,
-    buffer: &'static mut Buffer,
+    screen: [Row; BUFFER_HEIGHT],
+    visible: bool,
+    col: Col,
+    scrollback: Scrollback,
+    esc: Esc,
+    bold: bool,
 }
 
 impl Writer {
+    /// Creates a new, hidden writer with a blank screen.
+    pub const fn new() -> Self {
+        Self {
+            row_position: BUFFER_HEIGHT - 1,
+            column_position: 0,
+            screen: [[ScreenChar::new(ASCII_BLANK, ColourCode::new(FG_COL, BG_COL)); BUFFER_WIDTH];
+                BUFFER_HEIGHT],
+            visible: false,
+            col: Col::new(FG_COL, BG_COL),
+            scrollback: Scrollback::new(),
+            esc: Esc::None,
+            bold: false,
+        }
+    }
+
+    /// Shows or hides the writer.
+    ///
+    /// A writer that becomes visible copies its screen into the VGA text buffer and takes over the
+    /// hardware cursor.
+    ///
+    /// # Arguments
+    ///
+    /// * `visible`: Whether the output should be mirrored into the VGA text buffer.
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+        if visible {
+            self.render();
+            self.update_cursor();
+        }
+    }
+
+    /// Copies the status bar and the current viewport (the live screen or the scrollback history) into
+    /// the VGA text buffer.
+    fn render(&self) {
+        if !self.visible {
+            return;
+        }
+        for row in 0..BUFFER_HEIGHT {
+            for (col, c) in self.shown(row).iter().enumerate() {
+                show(row, col, *c);
+            }
+        }
+    }
+
+    /// Returns the row of the status bar or the viewport shown at a screen row.
+    fn shown(&self, row: usize) -> Row {
+        let max = self.scrollback.len();
+        let start = max - self.scrollback.offset;
+        if row < STATUS_ROWS {
+            self.screen[row]
+        } else if start + row - STATUS_ROWS < max {
+            self.scrollback.row(start + row - STATUS_ROWS)
+        } else {
+            self.screen[start + row - max]
+        }
+    }
+
+    /// Writes a character into the screen and mirrors it into the VGA text buffer if it is shown.
+    fn put(&mut self, row: usize, col: usize, c: ScreenChar) {
+        self.screen[row][col] = c;
+        if self.visible && (self.scrollback.offset == 0 || row < STATUS_ROWS) {
+            show(row, col, c);
+        }
+    }
+
+    /// Replaces the contents of the status bar.
+    ///
+    /// # Arguments
+    ///
+    /// * `s`: The status text, it is cut off at the end of the row.
+    /// * `colour`: The colour of the status bar.
+    pub fn status(&mut self, s: &str, colour: ColourCode) {
+        if STATUS_ROWS == 0 {
+            return;
+        }
+        let mut glyphs = s
+            .chars()
+            .map(|c| cp437::encode(c).unwrap_or(cp437::PLACEHOLDER));
+        for col in 0..BUFFER_WIDTH {
+            let glyph = glyphs.next().unwrap_or(ASCII_BLANK);
+            self.put(0, col, ScreenChar::new(glyph, colour));
+        }
+    }
+
+    /// Returns a blank character in the current colour.
+    fn blank(&self) -> ScreenChar {
+        ScreenChar::new(ASCII_BLANK, self.col.get())
+    }
+
     /// Writes a single byte to the VGA text buffer.
     ///
     /// This function writes a single byte to the VGA text buffer at the current position. It handles
@@ -260,52 +529,231 @@ impl Writer {
         match byte {
             b'\0' => {} // Ignore
             b'\n' => self.nl(),
-            byte => {
-                if self.column_position >= BUFFER_WIDTH {
-                    self.nl();
-                }
-
-                let row = BUFFER_HEIGHT - 1;
-                let col = self.column_position;
+            byte => self.wg(byte),
+        }
+    }
 
-                self.buffer.chars[row][col].write(ScreenChar::new(byte, COL.lock().get()));
-                self.column_position += 1;
-            }
+    /// Writes a single code page 437 glyph to the VGA text buffer.
+    ///
+    /// Unlike `wb` no byte is treated as a control character, so the glyphs of the code points below 0x20
+    /// can be written as well. It wraps to the next line when the current line is full.
+    ///
+    /// # Arguments
+    ///
+    /// * `glyph`: The code point of the glyph to be written to the buffer.
+    fn wg(&mut self, glyph: u8) {
+        if self.column_position >= BUFFER_WIDTH {
+            self.nl();
         }
+
+        let row = self.row_position;
+        let col = self.column_position;
+
+        self.put(row, col, ScreenChar::new(glyph, self.col.get()));
+        self.column_position += 1;
     }
 
     /// Writes a string to the VGA text buffer.
     ///
-    /// This function writes a string to the VGA text buffer. It processes each byte in the string,
-    /// ensuring that only printable ASCII characters or newline characters are written to the buffer.
+    /// This function writes a string to the VGA text buffer. It processes each character in the string,
+    /// translating it to its code page 437 glyph. Characters without a glyph are written as a placeholder.
+    /// ANSI escape sequences are interpreted instead of being printed.
     ///
     /// # Arguments
     ///
     /// * `s`: The string to be written to the buffer.
     fn ws(&mut self, s: &str) {
-        for byte in s.bytes() {
-            match byte {
-                // printable ASCII byte or newline
-                0x20..=0x7e | b'\n' => self.wb(byte),
-                // not part of printable ASCII range
-                _ => self.wb(0xfe),
+        for c in s.chars() {
+            match self.esc {
+                Esc::None => match c {
+                    '\x1b' => self.esc = Esc::Escape,
+                    '\r' => self.column_position = 0,
+                    '\0' | '\n' => self.wb(c as u8),
+                    _ => self.wg(cp437::encode(c).unwrap_or(cp437::PLACEHOLDER)),
+                },
+                Esc::Escape => match c {
+                    '[' => {
+                        self.esc = Esc::Csi {
+                            params: [0; ANSI_MAX_PARAMS],
+                            len: 0,
+                            private: false,
+                        }
+                    }
+                    // Unsupported sequence, drop it
+                    _ => self.esc = Esc::None,
+                },
+                Esc::Csi { .. } => match u8::try_from(c) {
+                    Ok(byte) => self.csi(byte),
+                    Err(_) => self.esc = Esc::None,
+                },
             }
         }
     }
 
-    /// Handles a newline character in the VGA text buffer.
+    /// Feeds a byte of a control sequence (`ESC [ params final`) to the parser.
     ///
-    /// This function processes a newline character, moving the contents of the buffer up by one line
-    /// and clearing the current line. It also resets the column position to the beginning of the line.
-    fn nl(&mut self) {
-        for row in 1..BUFFER_HEIGHT {
-            for col in 0..BUFFER_WIDTH {
-                let character = self.buffer.chars[row][col].read();
-                self.buffer.chars[row - 1][col].write(character);
+    /// # Arguments
+    ///
+    /// * `byte`: The byte following `ESC [`.
+    fn csi(&mut self, byte: u8) {
+        let Esc::Csi {
+            ref mut params,
+            ref mut len,
+            ref mut private,
+        } = self.esc
+        else {
+            return;
+        };
+        match byte {
+            b'0'..=b'9' => {
+                *len = (*len).max(1);
+                if let Some(param) = params.get_mut(*len - 1) {
+                    *param = param
+                        .saturating_mul(10)
+                        .saturating_add((byte - b'0') as u16);
+                }
+            }
+            b';' => *len = (*len).max(1).saturating_add(1),
+            b'<'..=b'?' => *private = true,
+            // Intermediate bytes
+            0x20..=0x2f => {}
+            // Final byte
+            0x40..=0x7e => {
+                let (params, len, private) = (*params, (*len).min(ANSI_MAX_PARAMS), *private);
+                self.esc = Esc::None;
+                if !private {
+                    self.dispatch(byte, &params[..len]);
+                }
+            }
+            // Malformed sequence, drop it
+            _ => self.esc = Esc::None,
+        }
+    }
+
+    /// Executes a complete control sequence.
+    ///
+    /// Supported are cursor movement (`A`, `B`, `C`, `D`, `G`, `H`, `f`), erase in display (`J`), erase in
+    /// line (`K`) and select graphic rendition (`m`). Everything else is ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `action`: The final byte of the sequence.
+    /// * `params`: The numeric parameters of the sequence.
+    fn dispatch(&mut self, action: u8, params: &[u16]) {
+        // Parameter with a default value for missing or zero parameters
+        let arg = |index: usize, default: usize| match params.get(index) {
+            Some(&param) if param != 0 => param as usize,
+            _ => default,
+        };
+        let (row, col) = self.cursor();
+        match action {
+            b'A' => self.set_cursor(row.saturating_sub(arg(0, 1)), col),
+            b'B' => self.set_cursor(row.saturating_add(arg(0, 1)), col),
+            b'C' => self.set_cursor(row, col.saturating_add(arg(0, 1))),
+            b'D' => self.set_cursor(row, col.saturating_sub(arg(0, 1))),
+            b'G' => self.set_cursor(row, arg(0, 1) - 1),
+            b'H' | b'f' => self.set_cursor(arg(0, 1) - 1, arg(1, 1) - 1),
+            b'J' => self.erase_display(arg(0, 0)),
+            b'K' => self.erase_line(arg(0, 0)),
+            b'm' => self.sgr(params),
+            _ => {}
+        }
+    }
+
+    /// Erases (parts of) the current line, `ESC [ n K`.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode`: 0 erases from the cursor to the end, 1 from the start to the cursor, 2 the whole line.
+    fn erase_line(&mut self, mode: usize) {
+        let (row, col) = (
+            self.row_position,
+            self.column_position.min(BUFFER_WIDTH - 1),
+        );
+        let cols = match mode {
+            0 => col..BUFFER_WIDTH,
+            1 => 0..col + 1,
+            _ => 0..BUFFER_WIDTH,
+        };
+        for col in cols {
+            self.put(row, col, self.blank());
+        }
+    }
+
+    /// Erases (parts of) the screen, `ESC [ n J`.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode`: 0 erases from the cursor to the end, 1 from the start to the cursor, 2 the whole screen.
+    fn erase_display(&mut self, mode: usize) {
+        let rows = match mode {
+            0 => self.row_position + 1..BUFFER_HEIGHT,
+            1 => STATUS_ROWS..self.row_position,
+            _ => STATUS_ROWS..BUFFER_HEIGHT,
+        };
+        for row in rows {
+            self.cr(row);
+        }
+        if mode < 2 {
+            self.erase_line(mode);
+        }
+    }
+
+    /// Applies a select graphic rendition sequence, `ESC [ n ; ... m`.
+    ///
+    /// Handles resetting (0), bold as bright colours (1, 22), foreground (30-37, 39, 90-97) and
+    /// background (40-47, 49, 100-107) colours.
+    ///
+    /// # Arguments
+    ///
+    /// * `params`: The SGR parameters, an empty list resets all attributes.
+    fn sgr(&mut self, params: &[u16]) {
+        let col = &mut self.col;
+        if params.is_empty() {
+            col.set_default();
+            self.bold = false;
+        }
+        for &param in params {
+            match param {
+                0 => {
+                    col.set_default();
+                    self.bold = false;
+                }
+                1 => {
+                    self.bold = true;
+                    if let Some(fg) = ANSI_COLOURS.iter().position(|&c| c == col.0) {
+                        col.set_fg(ANSI_COLOURS[fg | 8]);
+                    }
+                }
+                22 => self.bold = false,
+                30..=37 => {
+                    col.set_fg(ANSI_COLOURS[(param - 30) as usize | (self.bold as usize) << 3])
+                }
+                39 => col.set_fg(FG_COL),
+                40..=47 => col.set_bg(ANSI_COLOURS[(param - 40) as usize]),
+                49 => col.set_bg(BG_COL),
+                90..=97 => col.set_fg(ANSI_COLOURS[(param - 90) as usize | 8]),
+                100..=107 => col.set_bg(ANSI_COLOURS[(param - 100) as usize | 8]),
+                _ => {}
             }
         }
-        self.cr(BUFFER_HEIGHT - 1);
+    }
+
+    /// Handles a newline character in the VGA text buffer.
+    ///
+    /// This function processes a newline character, moving the cursor to the next row. On the bottom row
+    /// the contents of the scrolling region are moved up by one line and the last line is cleared instead.
+    /// It also resets the column position to the beginning of the line.
+    fn nl(&mut self) {
         self.column_position = 0;
+        if self.row_position < BUFFER_HEIGHT - 1 {
+            self.row_position += 1;
+            return;
+        }
+        self.scrollback.push(&self.screen[STATUS_ROWS]);
+        self.screen.copy_within(STATUS_ROWS + 1.., STATUS_ROWS);
+        self.screen[BUFFER_HEIGHT - 1] = [self.blank(); BUFFER_WIDTH];
+        self.render();
     }
 
     /// Clears a row in the VGA text buffer.
@@ -317,21 +765,110 @@ impl Writer {
     /// * `row`: The row index to be cleared.
     pub fn cr(&mut self, row: usize) {
         for col in 0..BUFFER_WIDTH {
-            self.buffer.chars[row][col].write(ScreenChar::blank());
+            self.put(row, col, self.blank());
         }
     }
 
     /// Clears a character in the VGA text buffer.
     ///
-    /// This function clears the character at the current position in the VGA text buffer.
+    /// This function clears the character before the cursor and moves the cursor onto it. At the start of a
+    /// row it continues at the end of the previous one, the status bar rows are never entered.
     pub fn cc(&mut self) {
-        let col = match self.column_position {
-            0..=2 => self.column_position,
-            3..=usize::MAX => self.column_position - 1,
-            _ => 1, // Impossible, but to make the compiler happy...
+        if self.column_position == 0 {
+            if self.row_position <= STATUS_ROWS {
+                return;
+            }
+            self.row_position -= 1;
+            self.column_position = BUFFER_WIDTH;
+        }
+        self.column_position = self.column_position.min(BUFFER_WIDTH) - 1;
+        self.put(self.row_position, self.column_position, self.blank())
+    }
+
+    /// Returns the current cursor position as `(row, column)`.
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.row_position, self.column_position)
+    }
+
+    /// Moves the cursor (the text insertion point) to the given position.
+    ///
+    /// Positions outside of the screen are clamped to the last row or column, the status bar rows can not
+    /// be entered.
+    ///
+    /// # Arguments
+    ///
+    /// * `row`: The row index of the new position.
+    /// * `col`: The column index of the new position.
+    pub fn set_cursor(&mut self, row: usize, col: usize) {
+        self.row_position = row.clamp(STATUS_ROWS, BUFFER_HEIGHT - 1);
+        self.column_position = col.min(BUFFER_WIDTH - 1);
+    }
+
+    /// Writes a string at the given position without moving the cursor.
+    ///
+    /// Output that does not fit into the row wraps onto the next one, as with normal printing.
+    ///
+    /// # Arguments
+    ///
+    /// * `row`: The row index to start writing at.
+    /// * `col`: The column index to start writing at.
+    /// * `s`: The string to be written to the buffer.
+    pub fn write_at(&mut self, row: usize, col: usize, s: &str) {
+        let cursor = self.cursor();
+        self.set_cursor(row, col);
+        self.ws(s);
+        self.set_cursor(cursor.0, cursor.1);
+    }
+
+    /// Moves the blinking hardware cursor to the text insertion point.
+    ///
+    /// The hardware cursor is moved off-screen while the viewport shows the scrollback history. Hidden
+    /// writers leave the hardware cursor alone.
+    pub fn update_cursor(&self) {
+        if !self.visible {
+            return;
+        }
+        let pos = match self.scrollback.offset {
+            0 => self.row_position * BUFFER_WIDTH + self.column_position.min(BUFFER_WIDTH - 1),
+            _ => BUFFER_HEIGHT * BUFFER_WIDTH,
         };
-        self.column_position = col;
-        self.buffer.chars[BUFFER_HEIGHT - 1][col].write(ScreenChar::blank())
+        let mut addr: Port<u8> = Port::new(CRTC_ADDR_PORT);
+        let mut data: Port<u8> = Port::new(CRTC_DATA_PORT);
+        unsafe {
+            addr.write(0x0f);
+            data.write((pos & 0xff) as u8);
+            addr.write(0x0e);
+            data.write(((pos >> 8) & 0xff) as u8);
+        }
+    }
+
+    /// Allocates the scrollback history, rows scrolled off the screen before are not kept.
+    pub fn alloc_scrollback(&mut self) {
+        self.scrollback.alloc();
+    }
+
+    /// Moves the viewport through the scrollback history.
+    ///
+    /// Positive values scroll towards older lines, negative values towards the live screen. The
+    /// offset is clamped to the available history.
+    ///
+    /// # Arguments
+    ///
+    /// * `lines`: The number of lines to scroll by.
+    pub fn scroll(&mut self, lines: isize) {
+        let max = self.scrollback.len();
+        let offset = self.scrollback.offset.saturating_add_signed(lines).min(max);
+        if offset != self.scrollback.offset {
+            self.scrollback.offset = offset;
+            self.render();
+        }
+    }
+
+    /// Returns the viewport to the live screen if the user scrolled back through the history.
+    fn snap(&mut self) {
+        if self.scrollback.offset > 0 {
+            self.scroll(-(self.scrollback.offset as isize));
+        }
     }
 }
 
@@ -342,40 +879,267 @@ impl fmt::Write for Writer {
     }
 }
 
+/// Initializes the VGA text console.
+///
+/// This function enables the hardware cursor and replaces the bootloader output with the screen of the
+/// active virtual terminal.
+pub fn init() {
+    let mut addr: Port<u8> = Port::new(CRTC_ADDR_PORT);
+    let mut data: Port<u8> = Port::new(CRTC_DATA_PORT);
+    interrupts::without_interrupts(|| {
+        unsafe {
+            addr.write(0x0a);
+            let start = data.read() & 0xc0;
+            data.write(start | CURSOR_START);
+            addr.write(0x0b);
+            let end = data.read() & 0xe0;
+            data.write(end | CURSOR_END);
+        }
+        VTS.lock().active().set_visible(true);
+    })
+}
+
+/// Writes a character into the VGA text buffer, inverting it if the mouse pointer is on the cell.
+fn show(row: usize, col: usize, mut c: ScreenChar) {
+    if POINTER.load(Ordering::Relaxed) == row * BUFFER_WIDTH + col {
+        c.colour_code = c.colour_code.inverted();
+    }
+    buffer().chars[row][col].write(c);
+}
+
+/// Moves the mouse pointer, a block drawn by inverting the colour of the cell under it.
+///
+/// # Arguments
+///
+/// * `cell`: The `(row, column)` of the pointer, or `None` to hide it.
+pub fn set_pointer(cell: Option<(usize, usize)>) {
+    let new = match cell {
+        Some((row, col)) if row < BUFFER_HEIGHT && col < BUFFER_WIDTH => row * BUFFER_WIDTH + col,
+        _ => NO_POINTER,
+    };
+    with_active(|writer| {
+        let old = POINTER.swap(new, Ordering::Relaxed);
+        if old == new || !writer.visible {
+            return;
+        }
+        // Redraw the cells the pointer left and entered
+        for cell in [old, new].into_iter().filter(|&cell| cell != NO_POINTER) {
+            let (row, col) = (cell / BUFFER_WIDTH, cell % BUFFER_WIDTH);
+            show(row, col, writer.shown(row)[col]);
+        }
+    })
+}
+
+/// Runs a closure on the writer of the active virtual terminal with interrupts disabled.
+fn with_active<R>(f: impl FnOnce(&mut Writer) -> R) -> R {
+    interrupts::without_interrupts(|| f(VTS.lock().active()))
+}
+
 #[doc(hidden)]
 pub fn clear_char() {
-    use x86_64::instructions::interrupts;
-    interrupts::without_interrupts(|| {
-        WRITER.lock().cc();
+    with_active(|writer| {
+        writer.snap();
+        writer.cc();
+        writer.update_cursor();
+        with_console(|console| console.backspace());
     })
 }
 #[doc(hidden)]
 pub fn clear_all() {
-    use x86_64::instructions::interrupts;
-    interrupts::without_interrupts(|| {
-        let mut writer = WRITER.lock();
-        for row in 1..BUFFER_HEIGHT {
+    with_active(|writer| {
+        writer.snap();
+        for row in STATUS_ROWS..BUFFER_HEIGHT {
             writer.cr(row);
         }
-        writer.cr(BUFFER_HEIGHT - 1);
-        writer.column_position = 0;
+        writer.set_cursor(STATUS_ROWS, 0);
+        writer.update_cursor();
+        with_console(|console| console.clear());
     })
 }
 
 #[doc(hidden)]
 pub fn _print(args: fmt::Arguments, clear: bool) {
     use core::fmt::Write;
-    use x86_64::instructions::interrupts;
     interrupts::without_interrupts(|| {
-        let mut writer = WRITER.lock();
+        // The lock can only be held here if the print interrupted the holder (e.g. an exception)
+        let Some(mut vts) = VTS.try_lock() else {
+            return _emergency(args);
+        };
+        let writer = vts.active();
+        writer.snap();
         if clear {
-            writer.cr(BUFFER_HEIGHT - 1);
+            let row = writer.row_position;
+            writer.cr(row);
             writer.column_position = 0;
         }
+        // Mirror the output onto the graphics text console
+        let colour = writer.col.get();
+        with_console(|console| {
+            console.set_colour(colour);
+            console.write_fmt(args).unwrap();
+        });
         writer.write_fmt(args).unwrap();
+        writer.update_cursor();
+    });
+}
+
+#[doc(hidden)]
+pub fn _lprint(vt: Option<usize>, label: &str, colours: (Colour, Colour), args: fmt::Arguments) {
+    use core::fmt::Write;
+    interrupts::without_interrupts(|| {
+        let Some(mut vts) = VTS.try_lock() else {
+            return _emergency(format_args!("{} {}\n", label, args));
+        };
+        let active = vts.index();
+        let writer = vts.get(vt.unwrap_or(active));
+        writer.snap();
+        let (fg, bg) = (writer.col.0, writer.col.1);
+        writer.col.set_fg(colours.0);
+        writer.ws(label);
+        writer.col.set_fg(colours.1);
+        writer.write_fmt(format_args!(" {}\n", args)).unwrap();
+        writer.col.set(fg, bg);
+        writer.update_cursor();
+        if vt.unwrap_or(active) == active {
+            with_console(|console| {
+                console.set_colour(ColourCode::new(colours.0, bg));
+                console.write_str(label).unwrap();
+                console.set_colour(ColourCode::new(colours.1, bg));
+                console.write_fmt(format_args!(" {}\n", args)).unwrap();
+            });
+        }
     });
 }
 
+/// Column of the emergency writer on the bottom row.
+static EMERGENCY_COL: AtomicUsize = AtomicUsize::new(0);
+/// Set once the panic handler printed, a nested panic only uses the emergency writer.
+static PANICKING: AtomicBool = AtomicBool::new(false);
+
+/// Lock-free writer drawing straight into the VGA text buffer.
+///
+/// It is used when the writer lock is held by the interrupted code, so the output of exception handlers
+/// and panics always reaches the screen. Text is written to the bottom row, which scrolls on new lines.
+struct Emergency;
+
+impl Emergency {
+    /// Scrolls the VGA text buffer (below the status bar) up by one row.
+    fn nl() {
+        let buffer = buffer();
+        for row in STATUS_ROWS + 1..BUFFER_HEIGHT {
+            for col in 0..BUFFER_WIDTH {
+                let c = buffer.chars[row][col].read();
+                buffer.chars[row - 1][col].write(c);
+            }
+        }
+        let blank = ScreenChar::new(ASCII_BLANK, ColourCode::new(FG_COL, BG_COL));
+        for col in 0..BUFFER_WIDTH {
+            buffer.chars[BUFFER_HEIGHT - 1][col].write(blank);
+        }
+        EMERGENCY_COL.store(0, Ordering::Relaxed);
+    }
+}
+
+impl fmt::Write for Emergency {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let colour = ColourCode::new(ERRM_COLOUR, BG_COL);
+        for c in s.chars() {
+            if c == '\n' {
+                Self::nl();
+                continue;
+            }
+            if EMERGENCY_COL.load(Ordering::Relaxed) >= BUFFER_WIDTH {
+                Self::nl();
+            }
+            let col = EMERGENCY_COL.fetch_add(1, Ordering::Relaxed);
+            let glyph = cp437::encode(c).unwrap_or(cp437::PLACEHOLDER);
+            buffer().chars[BUFFER_HEIGHT - 1][col].write(ScreenChar::new(glyph, colour));
+        }
+        Ok(())
+    }
+}
+
+#[doc(hidden)]
+pub fn _emergency(args: fmt::Arguments) {
+    use core::fmt::Write;
+    interrupts::without_interrupts(|| {
+        Emergency::nl();
+        let _ = Emergency.write_fmt(args);
+    });
+}
+
+/// Prints a panic message.
+///
+/// The machine is halted after a panic, so locks held by the panicking code will never be released. They
+/// are forcibly unlocked to print the message normally. A panic while printing the message falls back to
+/// the lock-free emergency writer.
+///
+/// # Arguments
+///
+/// * `args`: The panic message.
+pub fn panic_print(args: fmt::Arguments) {
+    interrupts::disable();
+    if PANICKING.swap(true, Ordering::Relaxed) {
+        return _emergency(args);
+    }
+    unsafe {
+        VTS.force_unlock();
+        force_unlock_console();
+    }
+    _lprint(None, "[PANIC]", (ERRP_COLOUR, ERRM_COLOUR), args);
+}
+
+/// Returns the current cursor position of the VGA console as `(row, column)`.
+pub fn cursor() -> (usize, usize) {
+    with_active(|writer| writer.cursor())
+}
+
+/// Moves the cursor of the VGA console to the given position.
+///
+/// Subsequent output is inserted at this position.
+///
+/// # Arguments
+///
+/// * `row`: The row index of the new position.
+/// * `col`: The column index of the new position.
+pub fn set_cursor(row: usize, col: usize) {
+    with_active(|writer| {
+        writer.snap();
+        writer.set_cursor(row, col);
+        writer.update_cursor();
+    })
+}
+
+/// Writes a string at the given position of the VGA console without moving the cursor.
+///
+/// # Arguments
+///
+/// * `row`: The row index to start writing at.
+/// * `col`: The column index to start writing at.
+/// * `s`: The string to be written.
+pub fn write_at(row: usize, col: usize, s: &str) {
+    with_active(|writer| {
+        writer.snap();
+        writer.write_at(row, col, s);
+    })
+}
+
+/// Scrolls the viewport one page up into the scrollback history.
+pub fn page_up() {
+    with_active(|writer| {
+        writer.scroll(BUFFER_HEIGHT as isize - 1);
+        writer.update_cursor();
+    })
+}
+
+/// Scrolls the viewport one page down towards the live screen.
+pub fn page_down() {
+    with_active(|writer| {
+        writer.scroll(1 - BUFFER_HEIGHT as isize);
+        writer.update_cursor();
+    })
+}
+
 /// A prelude for working with the VGA module.
 pub mod prelude {
     pub use crate::io::vga::{Colour, COL};
@@ -420,6 +1184,97 @@ fn test_vga() {
     );
 }
 #[test_case]
+fn test_scrollback() {
+    let col = ColourCode::new(FG_COL, BG_COL);
+    let mut row = [ScreenChar::new(ASCII_BLANK, col); BUFFER_WIDTH];
+    row[0] = ScreenChar::new(b'a', col);
+    let mut scrollback = Scrollback::new();
+    scrollback.push(&row);
+    test!(
+        "VGA Scrollback.push() unallocated",
+        assert_eq!(scrollback.len(), 0)
+    );
+    scrollback.alloc();
+    for _ in 0..SCROLLBACK_LINES {
+        scrollback.push(&[ScreenChar::new(ASCII_BLANK, col); BUFFER_WIDTH]);
+    }
+    scrollback.push(&row);
+    test!(
+        "VGA Scrollback.push() limit",
+        assert_eq!(scrollback.len(), SCROLLBACK_LINES)
+    );
+    test!(
+        "VGA Scrollback.row()",
+        assert_eq!(scrollback.row(SCROLLBACK_LINES - 1), row)
+    );
+}
+#[test_case]
+fn test_status() {
+    let mut writer = Writer::new();
+    let colour = ColourCode::new(STATUS_FG, STATUS_BG);
+    writer.status("status", colour);
+    writer.ws("\n\n");
+    for _ in 0..BUFFER_HEIGHT {
+        writer.nl();
+    }
+    test!(
+        "VGA Writer.status() pinned",
+        assert_eq!(writer.screen[0][0], ScreenChar::new(b's', colour))
+    );
+    writer.set_cursor(0, 0);
+    test!(
+        "VGA Writer.set_cursor() status row",
+        assert_eq!(writer.cursor(), (STATUS_ROWS, 0))
+    );
+}
+#[test_case]
+fn test_cursor() {
+    let (row, col) = cursor();
+    set_cursor(3, 4);
+    test!("VGA set_cursor()", assert_eq!(cursor(), (3, 4)));
+    set_cursor(BUFFER_HEIGHT, BUFFER_WIDTH);
+    test!(
+        "VGA set_cursor() clamp",
+        assert_eq!(cursor(), (BUFFER_HEIGHT - 1, BUFFER_WIDTH - 1))
+    );
+    write_at(0, 0, "write_at");
+    test!(
+        "VGA write_at() keeps cursor",
+        assert_eq!(cursor(), (BUFFER_HEIGHT - 1, BUFFER_WIDTH - 1))
+    );
+    set_cursor(row, col);
+}
+#[test_case]
+fn test_ansi() {
+    let (row, col) = cursor();
+    print!("\x1b[31;42m");
+    test!(
+        "VGA ANSI SGR colours",
+        assert_eq!(
+            COL.lock().get(),
+            ColourCode::new(Colour::Red, Colour::Green)
+        )
+    );
+    print!("\x1b[1;94m");
+    test!(
+        "VGA ANSI SGR bright",
+        assert_eq!(
+            COL.lock().get(),
+            ColourCode::new(Colour::LightBlue, Colour::Green)
+        )
+    );
+    print!("\x1b[m");
+    test!(
+        "VGA ANSI SGR reset",
+        assert_eq!(COL.lock().get(), ColourCode::new(FG_COL, BG_COL))
+    );
+    print!("\x1b[5;10H");
+    test!("VGA ANSI cursor position", assert_eq!(cursor(), (4, 9)));
+    print!("\x1b[2A\x1b[3C");
+    test!("VGA ANSI cursor movement", assert_eq!(cursor(), (2, 12)));
+    set_cursor(row, col);
+}
+#[test_case]
 fn test_print() {
     test!(
         "Testing print!()",
@@ -467,3 +1322,38 @@ fn test_print() {
         )
     );
 }
+
+#[test_case]
+fn test_reentrant_print() {
+    let enabled = interrupts::are_enabled();
+    {
+        let col = COL.lock();
+        test!(
+            "COL.lock() disables interrupts",
+            assert!(!interrupts::are_enabled())
+        );
+        // Printing while the lock is held (as from an exception handler) must not deadlock
+        crate::println!("Nested print");
+        crate::eprintln!("Nested error");
+        drop(col);
+    }
+    test!(
+        "COL guard restores interrupts",
+        assert_eq!(interrupts::are_enabled(), enabled)
+    );
+}
+#[test_case]
+fn test_pointer() {
+    let cell = |row: usize, col: usize| buffer().chars[row][col].read().colour_code;
+    let colour = cell(BUFFER_HEIGHT - 1, 0);
+    set_pointer(Some((BUFFER_HEIGHT - 1, 0)));
+    test!(
+        "VGA set_pointer()",
+        assert_eq!(cell(BUFFER_HEIGHT - 1, 0), colour.inverted())
+    );
+    set_pointer(None);
+    test!(
+        "VGA set_pointer() hide",
+        assert_eq!(cell(BUFFER_HEIGHT - 1, 0), colour)
+    );
+}
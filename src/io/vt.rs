@@ -0,0 +1,138 @@
+//     ____ _               _  ___  ____
+//    / ___| |__   __ _  __| |/ _ \/ ___|
+//   | |   | '_ \ / _` |/ _` | | | \___ \
+//   | |___| | | | (_| | (_| | |_| |___) |
+//    \____|_| |_|\__,_|\__,_|\___/|____/
+//    https://github.com/NewDawn0/ChadOS
+//
+//   @Author: NewDawn0
+//   @Contributors: -
+//   @License: MIT
+//
+//   File: src/io/vt.rs
+//   Desc: Virtual terminals
+
+// RustDoc
+//! # Virtual Terminal Module
+//!
+//! This module multiplexes the VGA text console into several virtual terminals. Every terminal owns a
+//! `Writer` with its own screen, scrollback history and colour, only the active one is shown on the screen.
+//! The terminals are switched with Alt+F1 to Alt+Fn, the last one (`LOG_VT`) receives the kernel messages.
+//!
+//! For more information about ChadOS, visit [the ChadOS GitHub repository](https://github.com/NewDawn0/ChadOS).
+//!
+//! ## Author
+//!
+//! - [NewDawn0](https://github.com/NewDawn0)
+//!
+//! ## License
+//!
+//! This code is licensed under the MIT License. See the MIT License section below for details.
+//!
+//! # File: src/io/vt.rs
+//!
+//! This file defines the virtual terminals for ChadOS.
+
+// Imports
+#[cfg(test)]
+use crate::test;
+use crate::{
+    cfg::vga::{LOG_VT, VT_COUNT},
+    io::vga::Writer,
+};
+use spin::Mutex;
+use x86_64::instructions::interrupts;
+
+// Globals
+/// The global virtual terminals.
+pub static VTS: Mutex<Vts> = Mutex::new(Vts::new());
+
+/// The set of virtual terminals and the index of the one shown on the screen.
+pub struct Vts {
+    vts: [Writer; VT_COUNT],
+    active: usize,
+}
+
+impl Vts {
+    /// Creates the virtual terminals, the kernel log terminal is active until the shell takes over.
+    const fn new() -> Self {
+        const VT: Writer = Writer::new();
+        Self {
+            vts: [VT; VT_COUNT],
+            active: LOG_VT,
+        }
+    }
+
+    /// Returns the index of the active virtual terminal.
+    pub fn index(&self) -> usize {
+        self.active
+    }
+
+    /// Returns the writer of the active virtual terminal.
+    pub fn active(&mut self) -> &mut Writer {
+        &mut self.vts[self.active]
+    }
+
+    /// Returns the writer of the active virtual terminal for reading.
+    pub fn active_ref(&self) -> &Writer {
+        &self.vts[self.active]
+    }
+
+    /// Returns the writer of a virtual terminal.
+    ///
+    /// # Arguments
+    ///
+    /// * `vt`: The index of the virtual terminal, must be smaller than `VT_COUNT`.
+    pub fn get(&mut self, vt: usize) -> &mut Writer {
+        &mut self.vts[vt]
+    }
+
+    /// Returns the writers of all virtual terminals.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Writer> {
+        self.vts.iter_mut()
+    }
+
+    /// Shows another virtual terminal on the screen.
+    ///
+    /// # Arguments
+    ///
+    /// * `vt`: The index of the virtual terminal, out of range indices are ignored.
+    pub fn switch(&mut self, vt: usize) {
+        if vt >= VT_COUNT || vt == self.active {
+            return;
+        }
+        self.vts[self.active].set_visible(false);
+        self.active = vt;
+        self.vts[vt].set_visible(true);
+    }
+}
+
+/// Allocates the scrollback history of the virtual terminals, which needs the heap.
+pub fn init() {
+    interrupts::without_interrupts(|| VTS.lock().iter_mut().for_each(Writer::alloc_scrollback))
+}
+
+/// Shows another virtual terminal on the screen.
+///
+/// # Arguments
+///
+/// * `vt`: The index of the virtual terminal, out of range indices are ignored.
+pub fn switch(vt: usize) {
+    interrupts::without_interrupts(|| VTS.lock().switch(vt))
+}
+
+/// Returns the index of the active virtual terminal.
+pub fn active() -> usize {
+    interrupts::without_interrupts(|| VTS.lock().index())
+}
+
+// Tests
+#[test_case]
+fn test_vt() {
+    let vt = active();
+    switch(VT_COUNT);
+    test!("VT switch() out of range", assert_eq!(active(), vt));
+    switch((vt + 1) % VT_COUNT);
+    test!("VT switch()", assert_eq!(active(), (vt + 1) % VT_COUNT));
+    switch(vt);
+}
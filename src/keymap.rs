@@ -0,0 +1,389 @@
+//     ____ _               _  ___  ____
+//    / ___| |__   __ _  __| |/ _ \/ ___|
+//   | |   | '_ \ / _` |/ _` | | | \___ \
+//   | |___| | | | (_| | (_| | |_| |___) |
+//    \____|_| |_|\__,_|\__,_|\___/|____/
+//    https://github.com/NewDawn0/ChadOS
+//
+//   @Author: NewDawn0
+//   @Contributors: -
+//   @License: MIT
+//
+//   File: src/keymap.rs
+//   Desc: Data-driven keymaps
+
+// RustDoc
+//! # Keymap Module
+//!
+//! This module provides keyboard layouts described by data instead of code. A keymap maps the character keys
+//! to the characters of four layers (base, shift, altgr and shift+altgr), any of which may be a dead key.
+//! Keys outside the character block (return, the numeric keypad, ...) are the same in every keymap.
+//!
+//! Keymaps are written in a line based text format, see `src/keymaps/ch.kmap`. They are either compiled in
+//! (`BUILTIN`) or loaded at runtime from a QEMU firmware configuration file.
+//!
+//! For more information about ChadOS, visit [the ChadOS GitHub repository](https://github.com/NewDawn0/ChadOS).
+//!
+//! ## Author
+//!
+//! - [NewDawn0](https://github.com/NewDawn0)
+//!
+//! ## License
+//!
+//! This code is licensed under the MIT License. See the MIT License section below for details.
+//!
+//! # File: src/keymap.rs
+//!
+//! This file defines the keymaps for ChadOS.
+
+// Imports
+#[cfg(test)]
+use crate::test;
+use crate::{
+    cfg::keys::KEYMAP_SIZE,
+    io::fw_cfg,
+    keys::{KeyCode, Modifiers},
+};
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec,
+};
+use core::str::{self, FromStr};
+
+/// The compiled in keymaps, by name.
+pub const BUILTIN: [(&str, &str); 2] = [
+    ("ch", include_str!("keymaps/ch.kmap")),
+    ("nordic", include_str!("keymaps/nordic.kmap")),
+];
+
+/// The keys whose characters are defined by a keymap, in the order of their rows.
+const KEYS: [KeyCode; 48] = [
+    KeyCode::Oem8,
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+    KeyCode::Key0,
+    KeyCode::OemMinus,
+    KeyCode::OemPlus,
+    KeyCode::Q,
+    KeyCode::W,
+    KeyCode::E,
+    KeyCode::R,
+    KeyCode::T,
+    KeyCode::Y,
+    KeyCode::U,
+    KeyCode::I,
+    KeyCode::O,
+    KeyCode::P,
+    KeyCode::Oem4,
+    KeyCode::Oem6,
+    KeyCode::Oem7,
+    KeyCode::A,
+    KeyCode::S,
+    KeyCode::D,
+    KeyCode::F,
+    KeyCode::G,
+    KeyCode::H,
+    KeyCode::J,
+    KeyCode::K,
+    KeyCode::L,
+    KeyCode::Oem1,
+    KeyCode::Oem3,
+    KeyCode::Oem5,
+    KeyCode::Z,
+    KeyCode::X,
+    KeyCode::C,
+    KeyCode::V,
+    KeyCode::B,
+    KeyCode::N,
+    KeyCode::M,
+    KeyCode::OemComma,
+    KeyCode::OemPeriod,
+    KeyCode::Oem2,
+];
+
+/// The number of layers of a key: base, shift, altgr and shift+altgr.
+const LAYERS: usize = 4;
+
+/// What a key produces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sym {
+    /// A character.
+    Char(char),
+    /// A dead key, the accent is combined with the following key.
+    Dead(char),
+}
+
+/// A keyboard layout described by data.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    name: String,
+    keys: [[Option<Sym>; LAYERS]; KEYS.len()],
+}
+
+impl Keymap {
+    /// Returns the name of the keymap.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Looks up what a key produces.
+    ///
+    /// # Arguments
+    ///
+    /// * `code`: The pressed key.
+    /// * `mods`: The modifiers and lock keys at the time of the press.
+    ///
+    /// # Returns
+    ///
+    /// The character or dead key, or `None` if the key produces nothing in this layer.
+    pub fn get(&self, code: KeyCode, mods: Modifiers) -> Option<Sym> {
+        let Some(index) = KEYS.iter().position(|&key| key == code) else {
+            return fixed(code, mods.num).map(Sym::Char);
+        };
+        let layers = &self.keys[index];
+        let level = (mods.altgr as usize) << 1;
+        let sym = layers[level | mods.shift as usize];
+        // Caps lock only affects letters, shift reverts it
+        match layers[level] {
+            Some(Sym::Char(c)) if mods.caps && c.is_alphabetic() => match mods.shift {
+                false => Some(Sym::Char(upper(c))),
+                true if sym == Some(Sym::Char(upper(c))) => Some(Sym::Char(c)),
+                true => sym,
+            },
+            _ => sym,
+        }
+    }
+}
+
+impl FromStr for Keymap {
+    type Err = String;
+
+    /// Parses a keymap description.
+    ///
+    /// Lines starting with `#` are comments. The `name` line names the keymap, every other line holds a key
+    /// code (as named by `pc_keyboard`, e.g. `Oem4`) followed by up to four layers. A layer is a single
+    /// character, a code point like `U+00E9`, a dead key like `dead:^` or `none`.
+    fn from_str(s: &str) -> Result<Self, String> {
+        let mut name = None;
+        let mut keys = [[None; LAYERS]; KEYS.len()];
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let err = |msg: &str| format!("Keymap line {}: {}", i + 1, msg);
+            let mut tokens = line.split_whitespace();
+            let key = tokens.next().unwrap_or_default();
+            if key == "name" {
+                name = Some(
+                    tokens
+                        .next()
+                        .ok_or_else(|| err("missing name"))?
+                        .to_string(),
+                );
+                continue;
+            }
+            let index = KEYS
+                .iter()
+                .position(|code| format!("{:?}", code) == key)
+                .ok_or_else(|| err("unknown key"))?;
+            for (layer, token) in tokens.enumerate() {
+                let sym = keys[index]
+                    .get_mut(layer)
+                    .ok_or_else(|| err("too many layers"))?;
+                *sym = parse_sym(token).ok_or_else(|| err("invalid character"))?;
+            }
+        }
+        Ok(Self {
+            name: name.ok_or("Keymap has no name")?,
+            keys,
+        })
+    }
+}
+
+/// Parses a layer of a key.
+///
+/// # Returns
+///
+/// The symbol of the layer (`None` for `none`), or `None` if the token is invalid.
+fn parse_sym(token: &str) -> Option<Option<Sym>> {
+    match token {
+        "none" => Some(None),
+        _ => match token.strip_prefix("dead:") {
+            Some(accent) => parse_char(accent).map(|c| Some(Sym::Dead(c))),
+            None => parse_char(token).map(|c| Some(Sym::Char(c))),
+        },
+    }
+}
+
+/// Parses a single character or a code point like `U+00E9`.
+fn parse_char(token: &str) -> Option<char> {
+    if let Some(hex) = token.strip_prefix("U+") {
+        return u32::from_str_radix(hex, 16).ok().and_then(char::from_u32);
+    }
+    let mut chars = token.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => None,
+    }
+}
+
+/// Returns the upper case form of a letter, if it is a single character.
+fn upper(c: char) -> char {
+    let mut upper = c.to_uppercase();
+    match (upper.next(), upper.next()) {
+        (Some(u), None) => u,
+        _ => c,
+    }
+}
+
+/// Returns the character of a key which is the same in every keymap.
+///
+/// # Arguments
+///
+/// * `code`: The pressed key.
+/// * `num`: Whether num lock is on, the keypad produces digits only then.
+fn fixed(code: KeyCode, num: bool) -> Option<char> {
+    let c = match code {
+        KeyCode::Escape => '\u{1b}',
+        KeyCode::Backspace => '\u{8}',
+        KeyCode::Tab => '\t',
+        KeyCode::Return | KeyCode::NumpadEnter => '\n',
+        KeyCode::Spacebar => ' ',
+        KeyCode::Delete => '\u{7f}',
+        KeyCode::NumpadDivide => '/',
+        KeyCode::NumpadMultiply => '*',
+        KeyCode::NumpadSubtract => '-',
+        KeyCode::NumpadAdd => '+',
+        KeyCode::NumpadPeriod if num => '.',
+        KeyCode::NumpadPeriod => '\u{7f}',
+        _ if !num => return None,
+        KeyCode::Numpad0 => '0',
+        KeyCode::Numpad1 => '1',
+        KeyCode::Numpad2 => '2',
+        KeyCode::Numpad3 => '3',
+        KeyCode::Numpad4 => '4',
+        KeyCode::Numpad5 => '5',
+        KeyCode::Numpad6 => '6',
+        KeyCode::Numpad7 => '7',
+        KeyCode::Numpad8 => '8',
+        KeyCode::Numpad9 => '9',
+        _ => return None,
+    };
+    Some(c)
+}
+
+/// Returns a compiled in keymap.
+///
+/// # Arguments
+///
+/// * `name`: The name of the keymap, e.g. `ch`.
+pub fn builtin(name: &str) -> Option<Keymap> {
+    let (_, text) = BUILTIN
+        .iter()
+        .find(|(builtin, _)| builtin.eq_ignore_ascii_case(name.trim()))?;
+    // The compiled in keymaps are checked by the tests
+    text.parse().ok()
+}
+
+/// Loads a keymap description from a QEMU firmware configuration file, e.g. one passed with
+/// `-fw_cfg name=opt/chados/keymap,file=ch.kmap`.
+///
+/// # Arguments
+///
+/// * `file`: The name of the firmware configuration file.
+///
+/// # Returns
+///
+/// The keymap, or an error if the file is missing or invalid.
+pub fn load(file: &str) -> Result<Keymap, String> {
+    let mut buf = vec![0; KEYMAP_SIZE];
+    let len = fw_cfg::read_file(file, &mut buf).ok_or_else(|| format!("No such file {}", file))?;
+    str::from_utf8(&buf[..len])
+        .map_err(|_| "Keymap is not valid UTF-8".to_string())?
+        .parse()
+}
+
+// Tests
+#[test_case]
+fn test_keymap() {
+    let keymap = "name test\nQ q Q @\nOem4 ü dead:^ none U+20AC"
+        .parse::<Keymap>()
+        .unwrap();
+    let mods = |shift, altgr, caps| Modifiers {
+        shift,
+        altgr,
+        caps,
+        ..Modifiers::new()
+    };
+    test!("KEYMAP name()", assert_eq!(keymap.name(), "test"));
+    test!(
+        "KEYMAP get() base",
+        assert_eq!(
+            keymap.get(KeyCode::Q, mods(false, false, false)),
+            Some(Sym::Char('q'))
+        )
+    );
+    test!(
+        "KEYMAP get() altgr",
+        assert_eq!(
+            keymap.get(KeyCode::Q, mods(false, true, false)),
+            Some(Sym::Char('@'))
+        )
+    );
+    test!(
+        "KEYMAP get() dead key",
+        assert_eq!(
+            keymap.get(KeyCode::Oem4, mods(true, false, false)),
+            Some(Sym::Dead('^'))
+        )
+    );
+    test!(
+        "KEYMAP get() code point",
+        assert_eq!(
+            keymap.get(KeyCode::Oem4, mods(true, true, false)),
+            Some(Sym::Char('€'))
+        )
+    );
+    test!(
+        "KEYMAP get() caps lock",
+        assert_eq!(
+            keymap.get(KeyCode::Oem4, mods(false, false, true)),
+            Some(Sym::Char('Ü'))
+        )
+    );
+    test!(
+        "KEYMAP get() caps lock and shift",
+        assert_eq!(
+            keymap.get(KeyCode::Q, mods(true, false, true)),
+            Some(Sym::Char('q'))
+        )
+    );
+    test!(
+        "KEYMAP get() fixed",
+        assert_eq!(
+            keymap.get(KeyCode::Return, mods(false, false, false)),
+            Some(Sym::Char('\n'))
+        )
+    );
+    test!(
+        "KEYMAP from_str() unknown key",
+        assert!("name test\nFoo a".parse::<Keymap>().is_err())
+    );
+    test!(
+        "KEYMAP from_str() no name",
+        assert!("Q q".parse::<Keymap>().is_err())
+    );
+    for (name, text) in BUILTIN {
+        test!("KEYMAP BUILTIN", assert!(text.parse::<Keymap>().is_ok()));
+        test!("KEYMAP builtin()", assert!(builtin(name).is_some()));
+    }
+}
@@ -36,10 +36,42 @@
 #[cfg(test)]
 use crate::test;
 use crate::{
-    cfg::{interrupt::KEYBOARD_PORT, keys::LAYOUT},
+    cfg::{
+        interrupt::KEYBOARD_PORT,
+        keys::{
+            COMPOSE_KEY, KEYMAP_FW_CFG, KEY_SUBSCRIBERS, LAYOUT, LAYOUT_FW_CFG, REPEAT_DELAY,
+            REPEAT_RATE, SCANCODE_QUEUE, SOFT_REPEAT,
+        },
+        time::PIT_HZ,
+        vga::VT_COUNT,
+    },
+    compose::Compose,
+    info,
     interrupt::handler::set_irq_handler,
-    io::vga::clear_char,
+    io::{
+        fw_cfg,
+        ps2::{self, ACK, RESEND},
+        vga::{clear_char, page_down, page_up},
+        vt,
+    },
+    keymap::{self, Keymap, Sym},
     print,
+    time::{delay, Uptime},
+    warn,
+};
+use alloc::{boxed::Box, string::String};
+use conquer_once::spin::OnceCell;
+use core::{
+    future::Future,
+    pin::Pin,
+    str::{self, FromStr},
+    sync::atomic::{AtomicUsize, Ordering},
+    task::{Context as Cx, Poll},
+};
+use crossbeam_queue::ArrayQueue;
+use futures_util::{
+    stream::{Stream, StreamExt},
+    task::AtomicWaker,
 };
 use lazy_static::lazy_static;
 use pc_keyboard::{
@@ -47,7 +79,14 @@ use pc_keyboard::{
     Keyboard, ScancodeSet1,
 };
 use spin::{Mutex, RwLock};
-use x86_64::instructions::port::Port;
+use x86_64::instructions::{interrupts, port::Port};
+
+// Re-exports
+pub use pc_keyboard::{KeyCode, KeyState};
+
+// Types
+/// A slot of the key event subscribers.
+type Subscriber = Option<fn(&Key)>;
 
 // Macros
 macro_rules! layout {
@@ -58,6 +97,38 @@ macro_rules! layout {
 
 // Globals
 static KEYBOARD: Mutex<Layout> = Mutex::new(Layout::new(LAYOUT));
+/// The modifier and lock key state, tracked across key events.
+static MODS: Mutex<ModState> = Mutex::new(ModState::new());
+/// Scancodes read by the interrupt handler, waiting to be decoded by the `KeyStream`.
+static SCANCODES: OnceCell<ArrayQueue<u8>> = OnceCell::uninit();
+/// Scancodes the interrupt handler dropped because the queue was full or not created yet, reported by the
+/// `KeyStream`.
+static DROPPED: AtomicUsize = AtomicUsize::new(0);
+/// Wakes the task polling the `KeyStream` when a scancode arrives.
+static WAKER: AtomicWaker = AtomicWaker::new();
+/// The functions receiving every key event, see `subscribe`.
+static SUBSCRIBERS: RwLock<[Subscriber; KEY_SUBSCRIBERS]> = RwLock::new([None; KEY_SUBSCRIBERS]);
+/// The typematic (key repeat) settings.
+static TYPEMATIC: Mutex<Typematic> = Mutex::new(Typematic::new());
+/// Keyboard command setting the LEDs, followed by the LED bits.
+const SET_LEDS: u8 = 0xed;
+/// Keyboard command setting the typematic rate and delay, followed by the encoded settings.
+const SET_TYPEMATIC: u8 = 0xf3;
+/// The function keys in order, Alt+F1 switches to the first virtual terminal.
+const FKEYS: [KC; 12] = [
+    KC::F1,
+    KC::F2,
+    KC::F3,
+    KC::F4,
+    KC::F5,
+    KC::F6,
+    KC::F7,
+    KC::F8,
+    KC::F9,
+    KC::F10,
+    KC::F11,
+    KC::F12,
+];
 lazy_static! {
     /// A read-write lock containing the key handler function.
     pub static ref KEY_HANDLER: RwLock<fn(c: char, mods: Modifiers)> =
@@ -65,7 +136,7 @@ lazy_static! {
 }
 
 /// Enumeration of wrapped keyboard layouts.
-#[derive(Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum WrappedLayout {
     Us,
     Uk,
@@ -77,7 +148,49 @@ pub enum WrappedLayout {
     Dvp,
 }
 
+impl WrappedLayout {
+    /// All supported layouts.
+    pub const ALL: [WrappedLayout; 8] = [
+        WrappedLayout::Us,
+        WrappedLayout::Uk,
+        WrappedLayout::Azerty,
+        WrappedLayout::De,
+        WrappedLayout::Jis,
+        WrappedLayout::Colemak,
+        WrappedLayout::Dvorak,
+        WrappedLayout::Dvp,
+    ];
+
+    /// Returns the lower case name of the layout, as accepted by the `layout` command.
+    pub fn name(self) -> &'static str {
+        match self {
+            WrappedLayout::Us => "us",
+            WrappedLayout::Uk => "uk",
+            WrappedLayout::Azerty => "azerty",
+            WrappedLayout::De => "de",
+            WrappedLayout::Jis => "jis",
+            WrappedLayout::Colemak => "colemak",
+            WrappedLayout::Dvorak => "dvorak",
+            WrappedLayout::Dvp => "dvp",
+        }
+    }
+}
+
+impl FromStr for WrappedLayout {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        Self::ALL
+            .into_iter()
+            .find(|layout| layout.name().eq_ignore_ascii_case(s.trim()))
+            .ok_or(())
+    }
+}
+
 /// Enum representing keyboard layouts.
+///
+/// A `Keymap` layout still decodes the scancodes with a `pc_keyboard` keyboard, only the characters come
+/// from the keymap.
 pub enum Layout {
     Us(Keyboard<layouts::Us104Key, ScancodeSet1>),
     Uk(Keyboard<layouts::Uk105Key, ScancodeSet1>),
@@ -87,6 +200,7 @@ pub enum Layout {
     Colemak(Keyboard<layouts::Colemak, ScancodeSet1>),
     Dvorak(Keyboard<layouts::Dvorak104Key, ScancodeSet1>),
     Dvp(Keyboard<layouts::DVP104Key, ScancodeSet1>),
+    Keymap(Keyboard<layouts::Us104Key, ScancodeSet1>, Box<Keymap>),
 }
 
 impl Layout {
@@ -104,6 +218,26 @@ impl Layout {
         }
     }
 
+    /// Creates a layout taking its characters from a keymap.
+    pub fn from_keymap(keymap: Keymap) -> Self {
+        Layout::Keymap(layout!(Us104Key), Box::new(keymap))
+    }
+
+    /// Returns the name of the layout, as accepted by the `layout` command.
+    pub fn name(&self) -> &str {
+        match self {
+            Layout::Us(_) => WrappedLayout::Us.name(),
+            Layout::Uk(_) => WrappedLayout::Uk.name(),
+            Layout::Azerty(_) => WrappedLayout::Azerty.name(),
+            Layout::De(_) => WrappedLayout::De.name(),
+            Layout::Jis(_) => WrappedLayout::Jis.name(),
+            Layout::Colemak(_) => WrappedLayout::Colemak.name(),
+            Layout::Dvorak(_) => WrappedLayout::Dvorak.name(),
+            Layout::Dvp(_) => WrappedLayout::Dvp.name(),
+            Layout::Keymap(_, keymap) => keymap.name(),
+        }
+    }
+
     /// Adds a byte to the layout and returns a `KeyEvent` if one is available.
     fn add_byte(&mut self, code: u8) -> Result<Option<KeyEvent>, Error> {
         match self {
@@ -115,12 +249,14 @@ impl Layout {
             Layout::Colemak(inner) => inner.add_byte(code),
             Layout::Dvorak(inner) => inner.add_byte(code),
             Layout::Dvp(inner) => inner.add_byte(code),
+            Layout::Keymap(inner, _) => inner.add_byte(code),
         }
     }
 
     /// Processes a `KeyEvent` and returns a `DecodedKey`.
     fn process_keyevent(&mut self, event: KeyEvent) -> Option<DecodedKey> {
         match self {
+            Layout::Keymap(..) => None,
             Layout::Us(inner) => inner.process_keyevent(event),
             Layout::Uk(inner) => inner.process_keyevent(event),
             Layout::Azerty(inner) => inner.process_keyevent(event),
@@ -131,121 +267,751 @@ impl Layout {
             Layout::Dvp(inner) => inner.process_keyevent(event),
         }
     }
+
+    /// Returns the accents which are dead keys in a built-in layout, keymaps define their own dead keys.
+    fn accents(&self) -> &'static [char] {
+        match self {
+            Layout::De(_) => &['^', '´', '`'],
+            Layout::Azerty(_) => &['^', '¨'],
+            _ => &[],
+        }
+    }
+
+    /// Maps a `KeyEvent` to what it produces in the layout.
+    ///
+    /// # Arguments
+    ///
+    /// * `event`: The key event.
+    /// * `mods`: The modifiers including the event, only used by keymaps (the other layouts track them).
+    fn map(&mut self, event: KeyEvent, mods: Modifiers) -> Option<Sym> {
+        match self {
+            Layout::Keymap(_, keymap) if event.state != KeyState::Up => {
+                keymap.get(event.code, mods)
+            }
+            Layout::Keymap(..) => None,
+            layout => match layout.process_keyevent(event)? {
+                DecodedKey::Unicode(c) if layout.accents().contains(&c) => Some(Sym::Dead(c)),
+                DecodedKey::Unicode(c) => Some(Sym::Char(c)),
+                DecodedKey::RawKey(_) => None,
+            },
+        }
+    }
 }
 
 /// Initializes the keyboard handling for ChadOS.
+///
+/// The layout can be chosen at boot by name with the QEMU firmware configuration file `LAYOUT_FW_CFG`, or
+/// loaded from the keymap description in `KEYMAP_FW_CFG`, otherwise `LAYOUT` is used.
 pub fn init() {
+    let mut buf = [0; 16];
+    if let Some(len) = fw_cfg::read_file(LAYOUT_FW_CFG, &mut buf) {
+        let name = str::from_utf8(&buf[..len]).unwrap_or_default();
+        if !select_layout(name) {
+            warn!("Unknown keyboard layout {:?}", name);
+        }
+    }
+    if fw_cfg::read_file(KEYMAP_FW_CFG, &mut []).is_some() {
+        match keymap::load(KEYMAP_FW_CFG) {
+            Ok(keymap) => set_keymap(keymap),
+            Err(e) => warn!("{}", e),
+        }
+    }
+    interrupts::without_interrupts(|| set_leds(MODS.lock().leds()));
+    if let Err(e) = set_typematic(Typematic::new()) {
+        warn!("{}", e);
+    }
     set_irq_handler(1, key_handler)
 }
 
+/// Returns the name of the active keyboard layout.
+pub fn layout() -> String {
+    interrupts::without_interrupts(|| KEYBOARD.lock().name().into())
+}
+
+/// Returns the names of all layouts, the built-in ones followed by the compiled in keymaps.
+pub fn layout_names() -> impl Iterator<Item = &'static str> {
+    let builtin = WrappedLayout::ALL.into_iter().map(WrappedLayout::name);
+    builtin.chain(keymap::BUILTIN.iter().map(|(name, _)| *name))
+}
+
+/// Switches the keyboard layout, keys which are held down are released.
+///
+/// # Arguments
+///
+/// * `layout`: The new layout.
+pub fn set_layout(layout: WrappedLayout) {
+    switch(Layout::new(layout))
+}
+
+/// Switches to a layout taking its characters from a keymap, see `set_layout`.
+///
+/// # Arguments
+///
+/// * `keymap`: The keymap of the new layout.
+pub fn set_keymap(keymap: Keymap) {
+    switch(Layout::from_keymap(keymap))
+}
+
+/// Switches to a built-in layout or a compiled in keymap by name.
+///
+/// # Arguments
+///
+/// * `name`: The name of the layout, see `layout_names`.
+///
+/// # Returns
+///
+/// `false` if there is no layout with the name.
+pub fn select_layout(name: &str) -> bool {
+    if let Ok(layout) = name.parse() {
+        set_layout(layout);
+    } else if let Some(keymap) = keymap::builtin(name) {
+        set_keymap(keymap);
+    } else {
+        return false;
+    }
+    true
+}
+
+/// Replaces the active layout and resets the modifier state.
+fn switch(layout: Layout) {
+    let name = String::from(layout.name());
+    interrupts::without_interrupts(|| {
+        *KEYBOARD.lock() = layout;
+        // The new layout starts with the default lock key state
+        let mut mods = MODS.lock();
+        *mods = ModState::new();
+        set_leds(mods.leds());
+    });
+    info!("Keyboard layout set to {}", name);
+}
+
 /// Reads a scancode from the keyboard port.
 fn read_scancode() -> u8 {
     unsafe { Port::new(KEYBOARD_PORT).read() }
 }
 
+/// Sets the keyboard LEDs.
+///
+/// # Arguments
+///
+/// * `leds`: The LED bits, scroll lock (bit 0), num lock (bit 1) and caps lock (bit 2).
+fn set_leds(leds: u8) {
+    interrupts::without_interrupts(|| {
+        if let Err(e) = ps2::send(SET_LEDS).and_then(|_| ps2::send(leds)) {
+            warn!("Keyboard LED update failed: {:?}", e);
+        }
+    })
+}
+
+/// The typematic settings: how long a key is held before it repeats and how fast it repeats.
+///
+/// The repeats are generated by the keyboard, or in software from the timer ticks if `software` is set
+/// (the keyboard repeats are dropped then).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Typematic {
+    /// The delay before the first repeat in milliseconds, from 250 to 1000.
+    pub delay: usize,
+    /// The number of repeats per second, from 2 to 30.
+    pub rate: usize,
+    /// Whether the repeats are generated in software.
+    pub software: bool,
+}
+
+impl Typematic {
+    /// Creates the default settings, see `REPEAT_DELAY` and `REPEAT_RATE`.
+    pub const fn new() -> Self {
+        Self {
+            delay: REPEAT_DELAY,
+            rate: REPEAT_RATE,
+            software: SOFT_REPEAT,
+        }
+    }
+
+    /// Encodes the settings for the keyboard, the closest supported delay and rate are chosen.
+    ///
+    /// # Returns
+    ///
+    /// The delay in bits 5 and 6 and the rate in bits 0 to 4.
+    fn encode(self) -> u8 {
+        let delay = (self.delay.saturating_sub(125) / 250).min(3) as u8;
+        // The repeat period is (8 + A) * 2^B * 4.17ms, with A in bits 0 to 2 and B in bits 3 and 4
+        let rate_x10 =
+            |code: u8| 1_000_000 / ((8 + (code & 7) as usize) * (1 << (code >> 3)) * 417);
+        let rate = (0..32)
+            .min_by_key(|&code| rate_x10(code).abs_diff(self.rate * 10))
+            .unwrap_or_default();
+        delay << 5 | rate
+    }
+
+    /// Returns the delay before the first software repeat in timer ticks.
+    fn delay_ticks(self) -> usize {
+        self.delay * PIT_HZ as usize / 1000
+    }
+
+    /// Returns the interval between the software repeats in timer ticks.
+    fn interval_ticks(self) -> usize {
+        (PIT_HZ as usize / self.rate).max(1)
+    }
+}
+
+impl Default for Typematic {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the typematic settings.
+pub fn typematic() -> Typematic {
+    *TYPEMATIC.lock()
+}
+
+/// Changes the typematic settings, the keyboard is configured even if the repeats are generated in
+/// software. The settings are only kept if the keyboard accepted them.
+///
+/// # Arguments
+///
+/// * `settings`: The new settings.
+///
+/// # Returns
+///
+/// An error if the settings are out of range or the keyboard rejected them.
+pub fn set_typematic(settings: Typematic) -> Result<(), &'static str> {
+    if !(250..=1000).contains(&settings.delay) {
+        return Err("The repeat delay must be between 250 and 1000 ms");
+    }
+    if !(2..=30).contains(&settings.rate) {
+        return Err("The repeat rate must be between 2 and 30 Hz");
+    }
+    ps2::send(SET_TYPEMATIC)
+        .and_then(|_| ps2::send(settings.encode()))
+        .map_err(|e| {
+            warn!("Keyboard typematic update failed: {:?}", e);
+            "The keyboard rejected the typematic settings"
+        })?;
+    *TYPEMATIC.lock() = settings;
+    Ok(())
+}
+
+/// The software auto-repeat of the key held down.
+struct Repeater {
+    /// The key held down and the tick of its next repeat.
+    held: Option<(Key, usize)>,
+}
+
+impl Repeater {
+    /// Creates the auto-repeat without a key held down.
+    const fn new() -> Self {
+        Self { held: None }
+    }
+
+    /// Tracks the keys held down.
+    ///
+    /// # Arguments
+    ///
+    /// * `key`: The decoded key event.
+    /// * `settings`: The typematic settings.
+    /// * `now`: The uptime in ticks.
+    ///
+    /// # Returns
+    ///
+    /// The key event, or `None` if it is a keyboard repeat which is replaced by the software repeats.
+    fn track(&mut self, key: Key, settings: Typematic, now: usize) -> Option<Key> {
+        let held = self.held.map(|(held, _)| held.code);
+        if !settings.software {
+            self.held = None;
+            return Some(key);
+        }
+        match key.state {
+            KeyState::Up if held == Some(key.code) => self.held = None,
+            KeyState::Up => {}
+            _ if held == Some(key.code) => return None,
+            _ if is_modifier(key.code) => {}
+            _ => self.held = Some((key, now + settings.delay_ticks())),
+        }
+        Some(key)
+    }
+
+    /// Returns the held key if its repeat is due.
+    ///
+    /// # Arguments
+    ///
+    /// * `settings`: The typematic settings.
+    /// * `now`: The uptime in ticks.
+    ///
+    /// # Returns
+    ///
+    /// The key, or the tick of the next repeat if it is not due yet. `None` if no key repeats.
+    fn due(&mut self, settings: Typematic, now: usize) -> Option<Result<Key, usize>> {
+        let (key, next) = self.held.as_mut().filter(|_| settings.software)?;
+        if now < *next {
+            return Some(Err(*next));
+        }
+        *next = now + settings.interval_ticks();
+        Some(Ok(*key))
+    }
+}
+
+/// Returns whether a key is a modifier or lock key, which are never repeated in software.
+fn is_modifier(code: KC) -> bool {
+    matches!(
+        code,
+        KC::LShift
+            | KC::RShift
+            | KC::LControl
+            | KC::RControl
+            | KC::RControl2
+            | KC::LAlt
+            | KC::RAlt2
+            | KC::RAltGr
+            | KC::LWin
+            | KC::RWin
+            | KC::ScrollLock
+            | KC::NumpadLock
+            | KC::CapsLock
+    )
+}
+
+/// The modifier and lock key state.
+///
+/// The left and right modifier keys are tracked separately, so releasing one of them keeps the modifier
+/// active while the other is held down.
+struct ModState {
+    shift: [bool; 2],
+    ctrl: [bool; 2],
+    alt: [bool; 2],
+    meta: [bool; 2],
+    altgr: bool,
+    caps: bool,
+    num: bool,
+    scroll: bool,
+    /// The lock keys currently held down, auto-repeated presses must not toggle them again.
+    held: [bool; 3],
+}
+
+impl ModState {
+    /// Creates the initial state, only num lock is on (as in the layouts).
+    const fn new() -> Self {
+        Self {
+            shift: [false; 2],
+            ctrl: [false; 2],
+            alt: [false; 2],
+            meta: [false; 2],
+            altgr: false,
+            caps: false,
+            num: true,
+            scroll: false,
+            held: [false; 3],
+        }
+    }
+
+    /// Updates the state with a key event.
+    ///
+    /// # Arguments
+    ///
+    /// * `event`: The key event.
+    ///
+    /// # Returns
+    ///
+    /// `None` if the event is an auto-repeated lock key press which must be dropped, otherwise whether a
+    /// lock key was toggled.
+    fn update(&mut self, event: &KeyEvent) -> Option<bool> {
+        let down = event.state != KeyState::Up;
+        let (key, index) = match event.code {
+            KC::LShift => (&mut self.shift[0], None),
+            KC::RShift => (&mut self.shift[1], None),
+            KC::LControl => (&mut self.ctrl[0], None),
+            KC::RControl | KC::RControl2 => (&mut self.ctrl[1], None),
+            KC::LAlt => (&mut self.alt[0], None),
+            KC::RAlt2 => (&mut self.alt[1], None),
+            KC::LWin => (&mut self.meta[0], None),
+            KC::RWin => (&mut self.meta[1], None),
+            KC::RAltGr => (&mut self.altgr, None),
+            KC::ScrollLock => (&mut self.scroll, Some(0)),
+            KC::NumpadLock => (&mut self.num, Some(1)),
+            KC::CapsLock => (&mut self.caps, Some(2)),
+            _ => return Some(false),
+        };
+        let Some(index) = index else {
+            *key = down;
+            return Some(false);
+        };
+        let held = core::mem::replace(&mut self.held[index], down);
+        match (down, held) {
+            (true, true) => None,
+            (true, false) => {
+                *key = !*key;
+                Some(true)
+            }
+            _ => Some(false),
+        }
+    }
+
+    /// Returns the modifiers delivered with a key.
+    fn modifiers(&self) -> Modifiers {
+        Modifiers {
+            shift: self.shift.contains(&true),
+            alt: self.alt.contains(&true),
+            ctrl: self.ctrl.contains(&true),
+            meta: self.meta.contains(&true),
+            caps: self.caps,
+            num: self.num,
+            altgr: self.altgr,
+            ..Modifiers::new()
+        }
+    }
+
+    /// Returns the LED bits of the lock keys.
+    fn leds(&self) -> u8 {
+        self.scroll as u8 | (self.num as u8) << 1 | (self.caps as u8) << 2
+    }
+}
+
 /// Modifiers for keyboard input.
+///
+/// The modifier and lock keys reflect the state while the key was pressed, the remaining fields describe
+/// the key itself.
+#[derive(Debug, Clone, Copy)]
 #[repr(packed)]
 pub struct Modifiers {
-    // Standard
-    pub shift: bool, // byte 1
-    pub alt: bool,   // byte 2
-    pub ctrl: bool,  // byte 3
-    pub meta: bool,  // byte 4
-    // Non-Standard: to fill the 8-bits
-    pub clear: bool, // byte 5
-    pub tab: bool,   // byte 6
-    pub enter: bool, // byte 7
-    pub caps: bool,  // byte 8
+    // Modifier and lock keys
+    pub shift: bool,
+    pub alt: bool,
+    pub ctrl: bool,
+    pub meta: bool,
+    pub altgr: bool,
+    pub caps: bool,
+    pub num: bool,
+    // The key itself
+    /// Backspace.
+    pub clear: bool,
+    pub tab: bool,
+    pub enter: bool,
+    /// The key if it has no character (arrows, Home, End, ...), it is delivered as `'\0'`.
+    pub key: Option<KeyCode>,
 }
 impl Modifiers {
     /// Creates a new instance of `Modifiers` with default values.
     pub const fn new() -> Self {
         Self {
-            // Standard
-            shift: false, // byte 1
-            alt: false,   // byte 2
-            ctrl: false,  // byte 3
-            meta: false,  // byte 4
-            // Non-Standard: to fill the 8-bits
-            clear: false, // byte 5
-            tab: false,   // byte 6
-            enter: false, // byte 7
-            caps: false,  // byte 8
+            // Modifier and lock keys
+            shift: false,
+            alt: false,
+            ctrl: false,
+            meta: false,
+            altgr: false,
+            caps: false,
+            num: false,
+            // The key itself
+            clear: false,
+            tab: false,
+            enter: false,
+            key: None,
         }
     }
 }
 
+impl Default for Modifiers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A key event, delivered to the subscribers for every press, auto-repeat and release of any key.
+#[derive(Debug, Clone, Copy)]
+pub struct Key {
+    /// The physical key.
+    pub code: KeyCode,
+    /// Whether the key was pressed or released.
+    pub state: KeyState,
+    /// The modifiers at the time of the event.
+    pub mods: Modifiers,
+    /// The character of the key in the active layout, `None` for releases and keys without one (arrows,
+    /// function keys, ...). Letters pressed with ctrl are delivered as the letter.
+    pub c: Option<char>,
+    /// Whether the key is a dead key, `c` is then the accent it adds to the following key.
+    pub dead: bool,
+}
+
+/// A handle to a key event subscription, see `unsubscribe`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Subscription(usize);
+
+/// Subscribes a function to all key events.
+///
+/// The function is called in interrupt context, so it should return quickly.
+///
+/// # Arguments
+///
+/// * `f`: The function receiving the key events.
+///
+/// # Returns
+///
+/// The handle for `unsubscribe`, or an error if there are too many subscribers.
+pub fn subscribe(f: fn(&Key)) -> Result<Subscription, &'static str> {
+    interrupts::without_interrupts(|| {
+        let mut subscribers = SUBSCRIBERS.write();
+        let index = subscribers
+            .iter()
+            .position(Option::is_none)
+            .ok_or("Too many key subscribers")?;
+        subscribers[index] = Some(f);
+        Ok(Subscription(index))
+    })
+}
+
+/// Removes a key event subscription.
+///
+/// # Arguments
+///
+/// * `sub`: The handle returned by `subscribe`.
+pub fn unsubscribe(sub: Subscription) {
+    interrupts::without_interrupts(|| SUBSCRIBERS.write()[sub.0] = None)
+}
+
+/// Delivers a key event to all subscribers.
+fn publish(key: &Key) {
+    // Copy the subscribers, so they may subscribe or unsubscribe themselves
+    let subscribers = *SUBSCRIBERS.read();
+    for f in subscribers.iter().flatten() {
+        f(key);
+    }
+}
+
 /// Default key handler function that prints characters to the console.
 fn default_key_handler(c: char, mods: Modifiers) {
     if mods.clear {
         clear_char();
-    } else if mods.caps || mods.shift {
-        print!("{}", c.to_uppercase());
+    } else if mods.ctrl && c.is_ascii_alphabetic() {
+        print!("^{}", c.to_ascii_uppercase());
     } else {
         print!("{}", c)
     }
 }
 
 /// Keyboard interrupt handler function.
+///
+/// The scancode is only queued, it is decoded by the task polling the `KeyStream`. Dropped scancodes are
+/// only counted, as printing from interrupt context could deadlock.
 fn key_handler() {
-    let mut keyboard = KEYBOARD.lock();
     let code = read_scancode();
-    if let Ok(Some(event)) = keyboard.add_byte(code) {
-        let mut flags = Modifiers::new();
-        match event.code {
-            KC::LAlt | KC::RAlt2 => {
-                flags.alt = true;
-            }
-            KC::LShift | KC::RShift => {
-                flags.shift = true;
-            }
-            KC::LControl | KC::RControl | KC::RControl2 => {
-                flags.ctrl = true;
-            }
-            KC::LWin | KC::RWin => {
-                flags.meta = true;
+    // Responses to LED updates are not scancodes
+    if matches!(code, ACK | RESEND) {
+        return;
+    }
+    match SCANCODES.try_get().map(|queue| queue.push(code)) {
+        Ok(Ok(())) => WAKER.wake(),
+        _ => {
+            DROPPED.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Decodes a scancode into a key event.
+///
+/// # Returns
+///
+/// The key event, or `None` if the scancode does not complete one.
+fn decode(code: u8) -> Option<Key> {
+    let mut keyboard = KEYBOARD.lock();
+    let event = keyboard.add_byte(code).ok()??;
+    let mut state = MODS.lock();
+    if state.update(&event)? {
+        set_leds(state.leds());
+    }
+    let mut mods = state.modifiers();
+    drop(state);
+    mods.enter = event.code == KC::Return;
+    let (code, key_state) = (event.code, event.state);
+    let ctrl = mods.ctrl && !matches!(code, KC::Backspace | KC::Tab | KC::Return);
+    let sym = keyboard.map(event, mods);
+    let c = match sym {
+        // Control characters of letters are delivered as the letter with ctrl set
+        Some(Sym::Char(c @ '\u{1}'..='\u{1a}')) if ctrl => Some((b'a' + c as u8 - 1) as char),
+        Some(Sym::Char(c)) if ctrl => Some(c.to_ascii_lowercase()),
+        Some(Sym::Char(c) | Sym::Dead(c)) => Some(c),
+        None => None,
+    };
+    Some(Key {
+        code,
+        state: key_state,
+        mods,
+        c,
+        dead: matches!(sym, Some(Sym::Dead(_))),
+    })
+}
+
+/// Delivers a key event to the subscribers, the key handler and the built-in key bindings.
+///
+/// # Arguments
+///
+/// * `key`: The key event.
+/// * `compose`: The state of the dead keys and compose sequences, which only apply to keys typed without
+///   ctrl and alt.
+fn dispatch(key: &Key, compose: &mut Compose) {
+    publish(key);
+    if key.state == KeyState::Up {
+        return;
+    }
+    let chars = match (key.c, key.code) {
+        (_, COMPOSE_KEY) => return compose.start(),
+        (Some(c), _) if key.mods.ctrl || key.mods.alt => [Some(c), None],
+        (Some(c), _) if key.dead => compose.feed(Sym::Dead(c)),
+        (Some(c), _) => compose.feed(Sym::Char(c)),
+        (None, KC::PageUp) => return page_up(),
+        (None, KC::PageDown) => return page_down(),
+        (None, code) if key.mods.alt => match FKEYS.iter().position(|&key| key == code) {
+            Some(vt) if vt < VT_COUNT => return vt::switch(vt),
+            _ => return,
+        },
+        (None, code) if is_modifier(code) => return,
+        (None, code) => {
+            let mut flags = key.mods;
+            flags.key = Some(code);
+            return KEY_HANDLER.read()('\0', flags);
+        }
+    };
+    for c in chars.into_iter().flatten() {
+        let mut flags = key.mods;
+        match c {
+            '\u{8}' => {
+                flags.clear = true;
+                KEY_HANDLER.read()('\0', flags);
             }
-            KC::Return => {
-                flags.enter = true;
+            '\t' => {
+                flags.tab = true;
+                KEY_HANDLER.read()('\0', flags);
             }
-            _ => {}
+            c => KEY_HANDLER.read()(c, flags),
         }
-        if let Some(key) = keyboard.process_keyevent(event) {
-            match key {
-                DecodedKey::Unicode('\u{8}') => {
-                    flags.clear = true;
-                    KEY_HANDLER.read()('\0', flags);
-                }
-                DecodedKey::Unicode('\t') => {
-                    flags.tab = true;
-                    KEY_HANDLER.read()('\0', flags);
+    }
+}
+
+/// A stream of key events, decoded from the scancodes queued by the interrupt handler.
+///
+/// The software auto-repeats are generated by the stream as well, see `Typematic`.
+pub struct KeyStream {
+    repeater: Repeater,
+}
+
+impl KeyStream {
+    /// Creates the key stream, there can only be one as it consumes the scancodes.
+    ///
+    /// # Panics
+    ///
+    /// If it is called more than once.
+    pub fn new() -> Self {
+        SCANCODES
+            .try_init_once(|| ArrayQueue::new(SCANCODE_QUEUE))
+            .expect("KeyStream::new should only be called once");
+        Self {
+            repeater: Repeater::new(),
+        }
+    }
+}
+
+impl Stream for KeyStream {
+    type Item = Key;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Cx) -> Poll<Option<Key>> {
+        let this = self.get_mut();
+        let queue = SCANCODES.try_get().expect("Scancode queue uninitialized");
+        let dropped = DROPPED.swap(0, Ordering::Relaxed);
+        if dropped > 0 {
+            warn!("Dropped {} scancodes of keyboard input", dropped);
+        }
+        loop {
+            let code = match queue.pop() {
+                Some(code) => code,
+                None => {
+                    WAKER.register(cx.waker());
+                    // A scancode may have arrived before the waker was registered
+                    match queue.pop() {
+                        Some(code) => {
+                            WAKER.take();
+                            code
+                        }
+                        None => return this.poll_repeat(cx),
+                    }
                 }
-                DecodedKey::Unicode(c) => KEY_HANDLER.read()(c, flags),
-                _ => {}
             };
+            let settings = typematic();
+            let key =
+                decode(code).and_then(|key| this.repeater.track(key, settings, Uptime::ticks()));
+            if let Some(key) = key {
+                return Poll::Ready(Some(key));
+            }
         }
     }
 }
 
+impl KeyStream {
+    /// Returns the software repeat of the held key if it is due, otherwise waits for the timer.
+    fn poll_repeat(&mut self, cx: &mut Cx) -> Poll<Option<Key>> {
+        match self.repeater.due(typematic(), Uptime::ticks()) {
+            Some(Ok(key)) => Poll::Ready(Some(key)),
+            Some(Err(next)) => {
+                // The delay registers the waker with the timer, it is woken on the next tick
+                let mut delay = delay(next.saturating_sub(Uptime::ticks()));
+                if Pin::new(&mut delay).poll(cx).is_ready() {
+                    cx.waker().wake_by_ref();
+                }
+                Poll::Pending
+            }
+            None => Poll::Pending,
+        }
+    }
+}
+
+/// Handles the keyboard input, nothing is delivered to the key handler and subscribers until this task
+/// runs on the executor.
+pub async fn run() {
+    let mut keys = KeyStream::new();
+    let mut compose = Compose::new();
+    while let Some(key) = keys.next().await {
+        dispatch(&key, &mut compose);
+    }
+}
+
 // Tests
 #[test_case]
 fn test_layout_creation() {
-    let layouts = [
-        WrappedLayout::Us,
-        WrappedLayout::Uk,
-        WrappedLayout::Azerty,
-        WrappedLayout::De,
-        WrappedLayout::Jis,
-        WrappedLayout::Colemak,
-        WrappedLayout::Dvorak,
-        WrappedLayout::Dvp,
-    ];
-    for layout in layouts.iter() {
+    for layout in WrappedLayout::ALL.iter() {
         let _ = Layout::new(*layout);
     }
     test!("KEYZ create layouts", assert_eq!(1, 1));
 }
 #[test_case]
+fn test_set_layout() {
+    let old = layout();
+    for name in [
+        "us", "UK", "de", "azerty", "jis", "colemak", "dvorak", "dvp",
+    ] {
+        let parsed = name.parse::<WrappedLayout>();
+        test!("KEYZ WrappedLayout::from_str()", assert!(parsed.is_ok()));
+        test!(
+            "KEYZ WrappedLayout.name()",
+            assert!(parsed.unwrap().name().eq_ignore_ascii_case(name))
+        );
+    }
+    test!(
+        "KEYZ WrappedLayout::from_str() invalid",
+        assert_eq!("qwertz".parse::<WrappedLayout>(), Err(()))
+    );
+    set_layout(WrappedLayout::De);
+    test!("KEYZ set_layout()", assert_eq!(layout(), "de"));
+    test!("KEYZ select_layout()", assert!(select_layout("ch")));
+    test!("KEYZ select_layout() keymap", assert_eq!(layout(), "ch"));
+    test!(
+        "KEYZ select_layout() invalid",
+        assert!(!select_layout("qwertz"))
+    );
+    test!(
+        "KEYZ layout_names()",
+        assert!(layout_names().any(|name| name == "nordic"))
+    );
+    select_layout(&old);
+}
+#[test_case]
 fn test_key_handler() {
     use core::mem::ManuallyDrop;
     use pc_keyboard::KeyState as KS;
@@ -260,3 +1026,129 @@ fn test_key_handler() {
         assert_eq!(res, Some(DecodedKey::Unicode('a')))
     )
 }
+#[test_case]
+fn test_modifiers() {
+    use pc_keyboard::KeyState as KS;
+    let mut state = ModState::new();
+    let event = |code, s| KeyEvent { code, state: s };
+    state.update(&event(KC::LShift, KS::Down));
+    state.update(&event(KC::RShift, KS::Down));
+    state.update(&event(KC::LShift, KS::Up));
+    test!("KEYZ ModState shift held", assert!(state.modifiers().shift));
+    state.update(&event(KC::RShift, KS::Up));
+    test!(
+        "KEYZ ModState shift released",
+        assert!(!state.modifiers().shift)
+    );
+    test!(
+        "KEYZ ModState caps toggle",
+        assert_eq!(state.update(&event(KC::CapsLock, KS::Down)), Some(true))
+    );
+    test!(
+        "KEYZ ModState caps repeat",
+        assert_eq!(state.update(&event(KC::CapsLock, KS::Down)), None)
+    );
+    state.update(&event(KC::CapsLock, KS::Up));
+    test!("KEYZ ModState caps on", assert!(state.modifiers().caps));
+    test!("KEYZ ModState.leds()", assert_eq!(state.leds(), 0b110));
+}
+#[test_case]
+fn test_subscribe() {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    static KEYS: AtomicUsize = AtomicUsize::new(0);
+    fn count(key: &Key) {
+        if key.code == KC::Home && key.c.is_none() {
+            KEYS.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+    let key = Key {
+        code: KC::Home,
+        state: KeyState::Up,
+        mods: Modifiers::new(),
+        c: None,
+        dead: false,
+    };
+    let sub = subscribe(count);
+    test!("KEYZ subscribe()", assert!(sub.is_ok()));
+    publish(&key);
+    test!(
+        "KEYZ publish()",
+        assert_eq!(KEYS.load(Ordering::Relaxed), 1)
+    );
+    unsubscribe(sub.unwrap());
+    publish(&key);
+    test!(
+        "KEYZ unsubscribe()",
+        assert_eq!(KEYS.load(Ordering::Relaxed), 1)
+    );
+}
+#[test_case]
+fn test_key_stream() {
+    use futures_util::task::noop_waker_ref;
+    let mut keys = KeyStream::new();
+    let mut cx = Cx::from_waker(noop_waker_ref());
+    test!(
+        "KEYZ KeyStream empty",
+        assert_eq!(
+            keys.poll_next_unpin(&mut cx).map(|key| key.is_some()),
+            Poll::Pending
+        )
+    );
+}
+#[test_case]
+fn test_typematic() {
+    let settings = |delay, rate, software| Typematic {
+        delay,
+        rate,
+        software,
+    };
+    test!(
+        "KEYZ Typematic.encode() default",
+        assert_eq!(settings(500, 11, false).encode(), 0x2b)
+    );
+    test!(
+        "KEYZ Typematic.encode() fastest",
+        assert_eq!(settings(250, 30, false).encode(), 0x00)
+    );
+    test!(
+        "KEYZ Typematic.encode() slowest",
+        assert_eq!(settings(1000, 2, false).encode(), 0x7f)
+    );
+    test!(
+        "KEYZ set_typematic() invalid",
+        assert!(set_typematic(settings(100, 11, false)).is_err())
+    );
+    let soft = settings(500, 10, true);
+    let key = |code, state| Key {
+        code,
+        state,
+        mods: Modifiers::new(),
+        c: None,
+        dead: false,
+    };
+    let mut repeater = Repeater::new();
+    repeater.track(key(KC::A, KeyState::Down), soft, 0);
+    test!(
+        "KEYZ Repeater keyboard repeat",
+        assert!(repeater
+            .track(key(KC::A, KeyState::Down), soft, 1)
+            .is_none())
+    );
+    test!(
+        "KEYZ Repeater delay",
+        assert!(matches!(repeater.due(soft, 10), Some(Err(50))))
+    );
+    test!(
+        "KEYZ Repeater repeat",
+        assert!(matches!(repeater.due(soft, 50), Some(Ok(_))))
+    );
+    test!(
+        "KEYZ Repeater interval",
+        assert!(matches!(repeater.due(soft, 51), Some(Err(60))))
+    );
+    repeater.track(key(KC::A, KeyState::Up), soft, 52);
+    test!(
+        "KEYZ Repeater release",
+        assert!(repeater.due(soft, 100).is_none())
+    );
+}
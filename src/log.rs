@@ -0,0 +1,415 @@
+//     ____ _               _  ___  ____
+//    / ___| |__   __ _  __| |/ _ \/ ___|
+//   | |   | '_ \ / _` |/ _` | | | \___ \
+//   | |___| | | | (_| | (_| | |_| |___) |
+//    \____|_| |_|\__,_|\__,_|\___/|____/
+//    https://github.com/NewDawn0/ChadOS
+//
+//   @Author: NewDawn0
+//   @Contributors: -
+//   @License: MIT
+//
+//   File: src/log.rs
+//   Desc: Kernel log with levels and sinks
+
+// RustDoc
+//! # Kernel Log Module
+//!
+//! This module provides the logging facade of ChadOS. Records are created with the `error!`, `warn!`,
+//! `info!`, `debug!` and `trace!` macros and carry their level, the uptime in ticks and the module path of
+//! the caller. Every record is handed to the registered sinks, each of which has its own level threshold.
+//! The built-in sinks print to the screen, write to the serial port and keep the records in an in-memory
+//! ring buffer, which can be dumped with the `dmesg` command.
+//!
+//! For more information about ChadOS, visit [the ChadOS GitHub repository](https://github.com/NewDawn0/ChadOS).
+//!
+//! ## Author
+//!
+//! - [NewDawn0](https://github.com/NewDawn0)
+//!
+//! ## License
+//!
+//! This code is licensed under the MIT License. See the MIT License section below for details.
+//!
+//! # File: src/log.rs
+//!
+//! This file defines the kernel log for ChadOS.
+
+// Imports
+#[cfg(test)]
+use crate::test;
+use crate::{
+    cfg::{log::*, vga::*},
+    io::{serial::SERIAL1, vga::_lprint},
+    time::Uptime,
+};
+use alloc::string::String;
+use core::{fmt, str::FromStr};
+use spin::Mutex;
+use x86_64::instructions::interrupts;
+
+// Macros
+
+/// Logs a record with the given level.
+///
+/// # Examples
+///
+/// ```
+/// log!(Level::Info, "Mapped {} pages", 4);
+/// ```
+#[macro_export]
+macro_rules! log {
+    ($level:expr, $($arg:tt)*) => ($crate::log::_log($level, module_path!(), format_args!($($arg)*)));
+}
+
+/// Logs an error, see `log!`.
+#[macro_export]
+macro_rules! error {
+    ($($arg:tt)*) => ($crate::log!($crate::log::Level::Error, $($arg)*));
+}
+
+/// Logs a warning, see `log!`.
+#[macro_export]
+macro_rules! warn {
+    ($($arg:tt)*) => ($crate::log!($crate::log::Level::Warn, $($arg)*));
+}
+
+/// Logs an informational message, see `log!`.
+#[macro_export]
+macro_rules! info {
+    ($($arg:tt)*) => ($crate::log!($crate::log::Level::Info, $($arg)*));
+}
+
+/// Logs a debug message, see `log!`.
+#[macro_export]
+macro_rules! debug {
+    ($($arg:tt)*) => ($crate::log!($crate::log::Level::Debug, $($arg)*));
+}
+
+/// Logs a trace message, see `log!`.
+#[macro_export]
+macro_rules! trace {
+    ($($arg:tt)*) => ($crate::log!($crate::log::Level::Trace, $($arg)*));
+}
+
+// Types
+/// A registered sink and its level threshold.
+type Slot = Option<(&'static dyn Sink, Level)>;
+
+// Globals
+/// The registered sinks.
+static SINKS: Mutex<[Slot; LOG_SINKS]> = Mutex::new(default_sinks());
+
+/// The ring buffer holding the most recent records.
+static RING: Mutex<Ring<LOG_BUF_SIZE>> = Mutex::new(Ring::new());
+
+/// The severity of a log record, from the most to the least severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    /// Returns the upper case name of the level.
+    pub fn name(self) -> &'static str {
+        match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+impl FromStr for Level {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        [
+            Level::Error,
+            Level::Warn,
+            Level::Info,
+            Level::Debug,
+            Level::Trace,
+        ]
+        .into_iter()
+        .find(|level| level.name().eq_ignore_ascii_case(s))
+        .ok_or(())
+    }
+}
+
+/// A single log record.
+pub struct Record<'a> {
+    /// The severity of the record.
+    pub level: Level,
+    /// The uptime in timer ticks when the record was created.
+    pub ticks: usize,
+    /// The module path of the caller.
+    pub module: &'static str,
+    /// The message.
+    pub args: fmt::Arguments<'a>,
+}
+
+/// A destination for log records.
+///
+/// Sinks are called with interrupts disabled and may be called from interrupt context, so they must not
+/// block on locks which are held by interrupted code.
+pub trait Sink: Sync {
+    /// Returns the name of the sink, used to change its threshold.
+    fn name(&self) -> &'static str;
+
+    /// Writes a record.
+    ///
+    /// # Arguments
+    ///
+    /// * `record`: The record to write.
+    fn write(&self, record: &Record);
+}
+
+/// Prints records to the screen, errors and warnings on the active virtual terminal and everything else on
+/// the kernel log terminal.
+pub struct VgaSink;
+
+impl Sink for VgaSink {
+    fn name(&self) -> &'static str {
+        "vga"
+    }
+
+    fn write(&self, record: &Record) {
+        let (vt, label, colours) = match record.level {
+            Level::Error => (None, "[ERROR]", (ERRP_COLOUR, ERRM_COLOUR)),
+            Level::Warn => (None, "[WARN]", (WARN_COLOUR, WARN_COLOUR)),
+            _ => (Some(LOG_VT), "[KERNEL]", (KERNELP_COLOUR, KERNELM_COLOUR)),
+        };
+        _lprint(vt, label, colours, record.args);
+    }
+}
+
+/// Writes records to the serial port.
+pub struct SerialSink;
+
+impl Sink for SerialSink {
+    fn name(&self) -> &'static str {
+        "serial"
+    }
+
+    fn write(&self, record: &Record) {
+        use core::fmt::Write;
+        if let Some(mut serial) = SERIAL1.try_lock() {
+            let _ = writeln!(serial, "{}", Line(record));
+        }
+    }
+}
+
+/// Keeps records in the in-memory ring buffer, see `dmesg`.
+pub struct RingSink;
+
+impl Sink for RingSink {
+    fn name(&self) -> &'static str {
+        "ring"
+    }
+
+    fn write(&self, record: &Record) {
+        use core::fmt::Write;
+        if let Some(mut ring) = RING.try_lock() {
+            let _ = writeln!(ring, "{}", Line(record));
+        }
+    }
+}
+
+/// Formats a record as a single line with its timestamp, level and module.
+struct Line<'a, 'b>(&'a Record<'b>);
+
+impl fmt::Display for Line<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let r = self.0;
+        write!(
+            f,
+            "[{:>8}] {:<5} {}: {}",
+            r.ticks, r.level, r.module, r.args
+        )
+    }
+}
+
+/// A byte ring buffer of text lines, the oldest lines are overwritten when it is full.
+struct Ring<const N: usize> {
+    buf: [u8; N],
+    head: usize,
+    len: usize,
+}
+
+impl<const N: usize> Ring<N> {
+    /// Creates an empty ring buffer.
+    const fn new() -> Self {
+        Self {
+            buf: [0; N],
+            head: 0,
+            len: 0,
+        }
+    }
+
+    /// Appends bytes, overwriting the oldest ones once the buffer is full.
+    fn push(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.buf[(self.head + self.len) % N] = b;
+            if self.len < N {
+                self.len += 1;
+            } else {
+                self.head = (self.head + 1) % N;
+            }
+        }
+    }
+
+    /// Returns the contents, a line cut by overwriting is dropped.
+    fn read(&self) -> String {
+        let bytes = (0..self.len).map(|i| self.buf[(self.head + i) % N]);
+        let text = String::from_utf8_lossy(&bytes.collect::<alloc::vec::Vec<u8>>()).into_owned();
+        match self.len == N {
+            true => text
+                .split_once('\n')
+                .map(|(_, rest)| rest.into())
+                .unwrap_or_default(),
+            false => text,
+        }
+    }
+
+    /// Removes all contents.
+    fn clear(&mut self) {
+        self.head = 0;
+        self.len = 0;
+    }
+}
+
+impl<const N: usize> fmt::Write for Ring<N> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push(s.as_bytes());
+        Ok(())
+    }
+}
+
+/// Returns the built-in sinks with their default thresholds.
+const fn default_sinks() -> [Slot; LOG_SINKS] {
+    let mut sinks: [Slot; LOG_SINKS] = [None; LOG_SINKS];
+    sinks[0] = Some((&VgaSink, VGA_LEVEL));
+    sinks[1] = Some((&SerialSink, SERIAL_LEVEL));
+    sinks[2] = Some((&RingSink, RING_LEVEL));
+    sinks
+}
+
+#[doc(hidden)]
+pub fn _log(level: Level, module: &'static str, args: fmt::Arguments) {
+    let record = Record {
+        level,
+        ticks: Uptime::ticks(),
+        module,
+        args,
+    };
+    interrupts::without_interrupts(|| {
+        // Copy the sinks, so a sink may log itself
+        let Some(sinks) = SINKS.try_lock().map(|sinks| *sinks) else {
+            return;
+        };
+        for (sink, threshold) in sinks.iter().flatten() {
+            if level <= *threshold {
+                sink.write(&record);
+            }
+        }
+    });
+}
+
+/// Registers a sink.
+///
+/// # Arguments
+///
+/// * `sink`: The sink to register.
+/// * `level`: The least severe level passed to the sink.
+///
+/// # Returns
+///
+/// An error if all sink slots are taken.
+pub fn add_sink(sink: &'static dyn Sink, level: Level) -> Result<(), &'static str> {
+    interrupts::without_interrupts(|| {
+        let mut sinks = SINKS.lock();
+        let slot = sinks
+            .iter_mut()
+            .find(|slot| slot.is_none())
+            .ok_or("Too many log sinks")?;
+        *slot = Some((sink, level));
+        Ok(())
+    })
+}
+
+/// Changes the level threshold of a sink.
+///
+/// # Arguments
+///
+/// * `name`: The name of the sink.
+/// * `level`: The least severe level passed to the sink.
+///
+/// # Returns
+///
+/// `false` if no sink with the name is registered.
+pub fn set_level(name: &str, level: Level) -> bool {
+    interrupts::without_interrupts(|| {
+        let mut sinks = SINKS.lock();
+        let sink = sinks
+            .iter_mut()
+            .flatten()
+            .find(|(sink, _)| sink.name() == name);
+        sink.map(|(_, threshold)| *threshold = level).is_some()
+    })
+}
+
+/// Returns the contents of the ring buffer, one record per line.
+pub fn dmesg() -> String {
+    interrupts::without_interrupts(|| RING.lock().read())
+}
+
+/// Clears the ring buffer.
+pub fn clear() {
+    interrupts::without_interrupts(|| RING.lock().clear())
+}
+
+// Tests
+#[test_case]
+fn test_ring() {
+    use core::fmt::Write;
+    let mut ring = Ring::<16>::new();
+    let _ = writeln!(ring, "one");
+    let _ = writeln!(ring, "two");
+    test!("LOG Ring.read()", assert_eq!(ring.read(), "one\ntwo\n"));
+    let _ = writeln!(ring, "three");
+    let _ = writeln!(ring, "four");
+    test!(
+        "LOG Ring.read() wrapped",
+        assert_eq!(ring.read(), "two\nthree\nfour\n")
+    );
+    ring.clear();
+    test!("LOG Ring.clear()", assert_eq!(ring.read(), ""));
+}
+
+#[test_case]
+fn test_log() {
+    test!(
+        "LOG Level::from_str()",
+        assert_eq!("warn".parse(), Ok(Level::Warn))
+    );
+    test!("LOG Level order", assert!(Level::Error < Level::Trace));
+    crate::debug!("Log test record");
+    test!(
+        "LOG dmesg()",
+        assert!(dmesg().contains("DEBUG ChadOS::log: Log test record"))
+    );
+    test!("LOG set_level()", assert!(!set_level("none", Level::Info)));
+}
@@ -68,10 +68,13 @@
 // Modules
 pub mod api;
 pub mod cfg;
+mod compose;
 mod console;
 mod interrupt;
 mod io;
+mod keymap;
 mod keys;
+mod log;
 mod mem;
 mod sched;
 #[cfg(test)]
@@ -83,11 +86,11 @@ mod util;
 // Imports
 extern crate alloc;
 use bootloader::{entry_point, BootInfo};
-use cfg::console::CMD_OK_COL;
+use cfg::{console::CMD_OK_COL, vga::LOG_VT};
 #[cfg(not(test))]
 use core::panic::PanicInfo;
-use io::vga::COL;
-use sched::Exec;
+use io::{vga::COL, vt};
+use sched::{Exec, Task};
 
 // Bootloader entrypoint
 entry_point!(kmain);
@@ -97,17 +100,23 @@ entry_point!(kmain);
 /// This function is called on system startup.
 fn kmain(boot_info: &'static BootInfo) -> ! {
     util::init(boot_info);
-    COL.lock().set_fg(CMD_OK_COL);
-    println!("CheapShell intialized");
-    print!("> "); // print console init
-    COL.lock().set_default();
+    // Start a shell on every terminal but the kernel log
+    for vt in 0..LOG_VT {
+        vt::switch(vt);
+        COL.lock().set_fg(CMD_OK_COL);
+        println!("CheapShell intialized");
+        print!("> "); // print console init
+        COL.lock().set_default();
+    }
+    vt::switch(0);
 
     // Tests
     #[cfg(test)]
     test_main();
     // Start the async executor
     let mut exec = Exec::new();
-    // exec.spawn(Task::new(scancode::print_keys()));
+    exec.spawn(Task::new(keys::run()));
+    exec.spawn(Task::new(io::status::run()));
     exec.run();
 }
 
@@ -117,7 +126,7 @@ fn kmain(boot_info: &'static BootInfo) -> ! {
 #[cfg(not(test))]
 #[panic_handler]
 fn panic(info: &PanicInfo) -> ! {
-    println!("{}", info);
+    io::vga::panic_print(format_args!("{}", info));
     unsafe {
         util::hlt_loop();
     }
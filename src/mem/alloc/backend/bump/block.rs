@@ -34,7 +34,7 @@
 
 // Imports
 use crate::cfg::mem::BLOCK_SIZES;
-use crate::mem::alloc::init::Locked;
+use crate::mem::alloc::init::{track, Locked};
 use alloc::alloc::{GlobalAlloc, Layout};
 use core::{
     mem,
@@ -87,7 +87,7 @@ impl FixedSizeBlockAlloc {
 unsafe impl GlobalAlloc for Locked<FixedSizeBlockAlloc> {
     unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
         let mut alloc = self.lock();
-        match list_index(&layout) {
+        let ptr = match list_index(&layout) {
             Some(index) => match alloc.list_heads[index].take() {
                 Some(node) => {
                     alloc.list_heads[index] = node.next.take();
@@ -100,10 +100,15 @@ unsafe impl GlobalAlloc for Locked<FixedSizeBlockAlloc> {
                 }
             },
             None => alloc.fallback_alloc(layout),
+        };
+        if !ptr.is_null() {
+            track(layout.size() as isize);
         }
+        ptr
     }
     unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
         let mut alloc = self.lock();
+        track(-(layout.size() as isize));
         match list_index(&layout) {
             Some(index) => {
                 let new_node = ListNode {
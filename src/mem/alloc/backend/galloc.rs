@@ -32,6 +32,7 @@
 //! This file contains the global allocator implementation for the Good Memory Allocator.
 
 // Imports
+use crate::mem::alloc::init::track;
 use core::alloc::GlobalAlloc;
 use good_memory_allocator::SpinLockedAllocator;
 
@@ -63,9 +64,14 @@ impl LockInterfaceSpinLockedAllocator {
 
 unsafe impl GlobalAlloc for LockInterfaceSpinLockedAllocator {
     unsafe fn alloc(&self, layout: core::alloc::Layout) -> *mut u8 {
-        self.inner.alloc(layout)
+        let ptr = self.inner.alloc(layout);
+        if !ptr.is_null() {
+            track(layout.size() as isize);
+        }
+        ptr
     }
     unsafe fn dealloc(&self, ptr: *mut u8, layout: core::alloc::Layout) {
+        track(-(layout.size() as isize));
         self.inner.dealloc(ptr, layout)
     }
 }
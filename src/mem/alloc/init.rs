@@ -38,6 +38,7 @@
 use crate::cfg::mem::{HEAP_SIZE, HEAP_START};
 #[cfg(test)]
 use crate::test;
+use core::sync::atomic::{AtomicUsize, Ordering};
 #[cfg(feature = "alloc-bump")]
 use spin::{Mutex, MutexGuard};
 use x86_64::{
@@ -54,6 +55,9 @@ use crate::mem::alloc::backend::bump::bump::ALLOC;
 #[cfg(feature = "alloc-galloc")]
 use crate::mem::alloc::backend::galloc::ALLOC;
 
+// Globals
+static HEAP_USED: AtomicUsize = AtomicUsize::new(0);
+
 #[cfg(feature = "alloc-bump")]
 pub struct Locked<T> {
     inner: Mutex<T>,
@@ -113,6 +117,22 @@ pub fn init(
     Ok(())
 }
 
+/// Records an allocation (positive `size`) or deallocation (negative `size`) of the active backend.
+pub fn track(size: isize) {
+    match size {
+        0.. => HEAP_USED.fetch_add(size as usize, Ordering::Relaxed),
+        _ => HEAP_USED.fetch_sub(size.unsigned_abs(), Ordering::Relaxed),
+    };
+}
+
+/// Returns the heap usage as `(used, free)` in bytes.
+///
+/// Only the requested sizes are counted, the bookkeeping and fragmentation of the backend are not.
+pub fn usage() -> (usize, usize) {
+    let used = HEAP_USED.load(Ordering::Relaxed);
+    (used, HEAP_SIZE.saturating_sub(used))
+}
+
 // Tests
 #[test_case]
 fn test_alloc() {
@@ -35,12 +35,22 @@
 
 // Imports
 use bootloader::bootinfo::{BootInfo, MemoryMap, MemoryRegionType};
+use core::sync::atomic::{AtomicU64, Ordering};
+use spin::Mutex;
 use x86_64::{
     registers::control::Cr3,
-    structures::paging::{FrameAllocator, OffsetPageTable, PageTable, PhysFrame, Size4KiB},
+    structures::paging::{
+        mapper::MapToError, FrameAllocator, Mapper, OffsetPageTable, Page, PageTable,
+        PageTableFlags as Flags, PhysFrame, Size4KiB,
+    },
     PhysAddr, VirtAddr,
 };
 
+// Virtual address the bootloader mapped the physical memory to
+static PHYS_MEM_OFFSET: AtomicU64 = AtomicU64::new(0);
+// Mapper and frame allocator kept for mapping pages after boot
+static MAPPER: Mutex<Option<(OffsetPageTable<'static>, BootInfoFrameAlloc)>> = Mutex::new(None);
+
 // Init func
 unsafe fn uinit(phys_mem_offset: VirtAddr) -> OffsetPageTable<'static> {
     OffsetPageTable::new(active_lvl4_pt(phys_mem_offset), phys_mem_offset)
@@ -49,11 +59,63 @@ unsafe fn uinit(phys_mem_offset: VirtAddr) -> OffsetPageTable<'static> {
 // Wrapped init fn
 pub fn init(boot_info: &'static BootInfo) -> (OffsetPageTable, BootInfoFrameAlloc) {
     let phys_mem_offset = VirtAddr::new(boot_info.physical_memory_offset);
+    PHYS_MEM_OFFSET.store(phys_mem_offset.as_u64(), Ordering::Relaxed);
     let mapper = unsafe { uinit(phys_mem_offset) };
     let frame_alloc = unsafe { BootInfoFrameAlloc::init(&boot_info.memory_map) };
     (mapper, frame_alloc)
 }
 
+/// Translates a physical address into the virtual address it is mapped to.
+///
+/// Only valid after `init`, the bootloader maps the whole physical memory at a fixed offset.
+///
+/// # Arguments
+///
+/// * `phys`: The physical address to translate.
+pub fn phys_to_virt(phys: PhysAddr) -> VirtAddr {
+    VirtAddr::new(PHYS_MEM_OFFSET.load(Ordering::Relaxed) + phys.as_u64())
+}
+
+/// Keeps the mapper and frame allocator returned by `init` for later use by `map_mmio`.
+///
+/// # Arguments
+///
+/// * `mapper`: The page table mapper.
+/// * `frame_alloc`: The frame allocator, frames handed out before are not reused.
+pub fn store(mapper: OffsetPageTable<'static>, frame_alloc: BootInfoFrameAlloc) {
+    *MAPPER.lock() = Some((mapper, frame_alloc));
+}
+
+/// Maps a range of device memory (e.g. a framebuffer) uncached at a virtual address.
+///
+/// Pages that are already mapped are left untouched, so a range can be mapped more than once.
+///
+/// # Arguments
+///
+/// * `phys`: The physical start address of the range.
+/// * `virt`: The virtual start address to map the range to.
+/// * `size`: The size of the range in bytes.
+///
+/// # Returns
+///
+/// Returns `Ok(())` if the range is mapped, or an error of type `MapToError` if mapping a page failed or
+/// `store` was not called yet.
+pub fn map_mmio(phys: PhysAddr, virt: VirtAddr, size: u64) -> Result<(), MapToError<Size4KiB>> {
+    let mut guard = MAPPER.lock();
+    let (mapper, frame_alloc) = guard.as_mut().ok_or(MapToError::FrameAllocationFailed)?;
+    let flags = Flags::PRESENT | Flags::WRITABLE | Flags::NO_CACHE;
+    for offset in (0..size).step_by(4096) {
+        let page: Page<Size4KiB> = Page::containing_address(virt + offset);
+        let frame = PhysFrame::containing_address(phys + offset);
+        match unsafe { mapper.map_to(page, frame, flags, frame_alloc) } {
+            Ok(flush) => flush.flush(),
+            Err(MapToError::PageAlreadyMapped(_)) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
 // Get level 4 PageTable
 unsafe fn active_lvl4_pt(phys_mem_offset: VirtAddr) -> &'static mut PageTable {
     let (lvl4_pt, _) = Cr3::read();
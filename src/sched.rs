@@ -33,17 +33,20 @@
 //! This file contains the task scheduling implementation.
 
 // Imports
+use crate::time;
 use alloc::{boxed::Box, collections::BTreeMap, sync::Arc, task::Wake};
-#[cfg(feature = "unused-task")]
-use core::sync::atomic::{AtomicU64, Ordering};
 use core::{
     arch::asm,
     future::Future,
     pin::Pin,
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
     task::{Context as Cx, Poll, Waker},
 };
 use crossbeam_queue::ArrayQueue;
 
+// Globals
+static TASKS: AtomicUsize = AtomicUsize::new(0);
+
 /// The main task scheduler for ChadOS.
 pub struct Exec {
     tasks: BTreeMap<TaskId, Task>,
@@ -53,7 +56,7 @@ pub struct Exec {
 
 /// Represents a task that can be scheduled and executed.
 pub struct Task {
-    _id: TaskId,
+    id: TaskId,
     future: Pin<Box<dyn Future<Output = ()>>>,
 }
 
@@ -63,10 +66,9 @@ impl Task {
     /// # Parameters
     ///
     /// - `future`: A future representing the task.
-    #[cfg(feature = "unused-task")]
     pub fn new(future: impl Future<Output = ()> + 'static) -> Self {
         Self {
-            _id: TaskId::new(),
+            id: TaskId::new(),
             future: Box::pin(future),
         }
     }
@@ -117,7 +119,6 @@ impl Wake for TaskWaker {
 /// Represents a task ID.
 #[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone, Copy)]
 struct TaskId(u64);
-#[cfg(feature = "unused-task")]
 impl TaskId {
     /// Generates a new unique task ID.
     fn new() -> Self {
@@ -126,6 +127,11 @@ impl TaskId {
     }
 }
 
+/// Returns the number of tasks spawned on executors that have not finished yet.
+pub fn task_count() -> usize {
+    TASKS.load(Ordering::Relaxed)
+}
+
 impl Exec {
     /// Creates a new `Exec` instance for task scheduling.
     pub fn new() -> Self {
@@ -141,18 +147,19 @@ impl Exec {
     /// # Parameters
     ///
     /// - `task`: The task to be spawned.
-    #[cfg(feature = "unused-task")]
     pub fn spawn(&mut self, task: Task) {
         let id = task.id;
         match self.tasks.insert(task.id, task) {
             Some(_) => panic!("Task with same id exists"),
             None => self.queue.push(id).expect("Queue full"),
         }
+        TASKS.fetch_add(1, Ordering::Relaxed);
     }
 
     /// Runs the task scheduler.
     pub fn run(&mut self) -> ! {
         loop {
+            time::wake_delays();
             self.ready();
             self.sleep();
         }
@@ -178,6 +185,7 @@ impl Exec {
                     // task done -> remove it and its cached waker
                     tasks.remove(&id);
                     cache.remove(&id);
+                    TASKS.fetch_sub(1, Ordering::Relaxed);
                 }
                 Poll::Pending => {}
             }
@@ -186,7 +194,8 @@ impl Exec {
     fn sleep(&self) {
         unsafe {
             asm!("cli");
-            if self.queue.is_empty() {
+            // A tick during `ready` may have made a delay due
+            if self.queue.is_empty() && !time::ticked() {
                 asm!("sti");
                 asm!("hlt");
             }
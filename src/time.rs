@@ -38,23 +38,56 @@
 #[cfg(test)]
 use crate::test;
 use crate::{
-    cfg::time::{PIT_ADDR_PORT, PIT_CMD_PORT, PIT_HZ},
+    cfg::time::{PIT_ADDR_PORT, PIT_CMD_PORT, PIT_HZ, TIMER_WAKERS},
     interrupt::handler::set_irq_handler,
 };
 use alloc::{format, string::String};
-use core::sync::atomic::{AtomicUsize, Ordering};
+use conquer_once::spin::OnceCell;
+use core::{
+    future::Future,
+    pin::Pin,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    task::{Context, Poll, Waker},
+};
+use crossbeam_queue::ArrayQueue;
 use x86_64::instructions::{interrupts, port::PortWriteOnly};
 
 // Globals
 static UPTIME_TICKS: AtomicUsize = AtomicUsize::new(0);
 static UPTIME_SECS: AtomicUsize = AtomicUsize::new(0);
+static WAKERS: OnceCell<ArrayQueue<Waker>> = OnceCell::uninit();
+static TICKED: AtomicBool = AtomicBool::new(false);
 
 /// Handles timer interrupts and increments the uptime counters.
+///
+/// The tasks waiting on a `Delay` are woken by `wake_delays` instead. Waking a task consumes its waker, and
+/// dropping the last reference to a waker frees it, which must not happen while the interrupted code may hold
+/// the allocator lock.
 fn timer_handler() {
     UPTIME_TICKS.fetch_add(1, Ordering::Relaxed);
     if UPTIME_TICKS.load(Ordering::Relaxed) % PIT_HZ as usize == 0 {
         UPTIME_SECS.fetch_add(1, Ordering::Relaxed);
     }
+    TICKED.store(true, Ordering::Relaxed);
+}
+
+/// Returns whether the timer ticked since the last call of `wake_delays`.
+pub fn ticked() -> bool {
+    TICKED.load(Ordering::Relaxed)
+}
+
+/// Wakes the tasks waiting on a `Delay` if the timer ticked since the last call.
+///
+/// Must be called from task context by the executor, not from an interrupt handler.
+pub fn wake_delays() {
+    if !TICKED.swap(false, Ordering::Relaxed) {
+        return;
+    }
+    if let Ok(wakers) = WAKERS.try_get() {
+        while let Some(waker) = wakers.pop() {
+            waker.wake();
+        }
+    }
 }
 
 /// Initializes the timer and sets the timer interrupt handler.
@@ -162,6 +195,38 @@ pub fn sleep(secs: usize) {
     }
 }
 
+/// A future that completes once a number of timer ticks has passed, the async counterpart of `sleep`.
+pub struct Delay {
+    until: usize,
+}
+
+/// Returns a future that completes after the specified number of timer ticks.
+///
+/// # Parameters
+///
+/// - `ticks`: The number of ticks to wait, `PIT_HZ` ticks are one second.
+pub fn delay(ticks: usize) -> Delay {
+    Delay {
+        until: Uptime::ticks() + ticks,
+    }
+}
+
+impl Future for Delay {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        if Uptime::ticks() >= self.until {
+            return Poll::Ready(());
+        }
+        let wakers = WAKERS.get_or_init(|| ArrayQueue::new(TIMER_WAKERS));
+        if wakers.push(cx.waker().clone()).is_err() {
+            // No free slot, poll again as soon as possible
+            cx.waker().wake_by_ref();
+        }
+        Poll::Pending
+    }
+}
+
 /// Makes the CPU wait until the next timer interrupt (HLT instruction).
 fn hlt() {
     let disabled = !interrupts::are_enabled();
@@ -37,27 +37,82 @@ use crate::{
     api::{
         asm::asm,
         io::println,
-        scripting::{parse, register, CmdArgs, CmdRes, FUNCS},
+        log::{self, Level},
+        scripting::{
+            completer, parse, register, register_ctx, CmdArgs, CmdCtx, CmdRes, CtxRes, COMPLETERS,
+            FUNCS,
+        },
         time::{self, Uptime},
     },
-    io::vga::clear_all,
+    cfg::gfx::VBE_RES,
+    compose,
+    console::history::HISTORY,
+    io::{
+        gfx::{
+            console::with_surface,
+            vbe,
+            vga::{mode, set_mode, Mode},
+        },
+        vga::clear_all,
+    },
+    keymap, keys,
+};
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
 };
-use alloc::string::{String, ToString};
 
 /// Initalizes all user functions
 pub fn init() {
     let mut funcs = FUNCS.write();
     register!(funcs, echo);
-    register!(funcs, tee);
+    register_ctx!(funcs, tee);
     register!(funcs, uptime);
     register!(funcs, clear);
     register!(funcs, sum);
     register!(funcs, asm_test);
     register!(funcs, sleep);
+    register!(funcs, vgamode);
+    register!(funcs, draw);
+    register!(funcs, dmesg);
+    register!(funcs, layout);
+    register!(funcs, compose);
+    register!(funcs, kbdrate);
+    register!(funcs, history);
+    // Argument completers
+    let mut completers = COMPLETERS.write();
+    completer!(completers, uptime, |_| words(&["-s", "-t", "-d"]));
+    completer!(completers, vgamode, |args| match args {
+        [] => words(&["text", "13h", "12h", "vbe"]),
+        _ => Vec::new(),
+    });
+    completer!(completers, dmesg, |args| match args {
+        [] => words(&["-c", "-n"]),
+        ["-n"] => words(&["error", "warn", "info", "debug", "trace"]),
+        _ => Vec::new(),
+    });
+    completer!(completers, layout, |args| match args {
+        [] => keys::layout_names()
+            .chain(["-f"])
+            .map(String::from)
+            .collect(),
+        _ => Vec::new(),
+    });
+    completer!(completers, kbdrate, |_| words(&["-d", "-r", "-s", "-k"]));
+    completer!(completers, history, |_| words(&["-c"]));
+}
+
+/// Returns the candidates of a completer.
+fn words(words: &[&str]) -> Vec<String> {
+    words.iter().map(|&word| String::from(word)).collect()
 }
 
 // @NOTE: A user function needs to have the function signature fn(CmdArgs) -> CmdRes otherwise it will not register
 // @NOTE: CmdArgs is a type alias for `&[&str]`
+// @NOTE: Output piped into such a function is appended to its arguments word by word, to get it as it is register a
+//        function with the signature fn(&CmdCtx) -> CtxRes with `register_ctx!` and read `CmdCtx::stdin`
+// @NOTE: An `Err(String)` has the exit status 1, functions taking a `CmdCtx` can fail with others through `CmdErr`
 // @NOTE: CmdRes is a type alias for `Result<Option<String>, String>`
 // @NOTE: To help parse the arg from a &str to whatever type use `parse!(<arg>, <type>)?;`
 
@@ -85,22 +140,22 @@ fn echo(args: CmdArgs) -> CmdRes {
 
 /// Example function: tee
 ///
-/// This function echoes the input arguments as a string and returns them.
+/// This function echoes the output piped into it, or the input arguments as a string, and returns it.
 ///
 /// # Arguments
 ///
-/// - `args`: A slice of `&str` representing the arguments to tee.
+/// - `ctx`: The calling context, its stdin or otherwise its arguments are teed.
 ///
 /// # Return
 ///
-/// Returns `Ok(Some(String))` containing the echoed input arguments.
-fn tee(args: CmdArgs) -> CmdRes {
-    if args.is_empty() {
-        println!();
-    } else {
-        println!("{}", args.join(" "));
-    }
-    Ok(Some(args.join(" ")))
+/// Returns `Ok(Some(String))` containing the echoed input.
+fn tee(ctx: &CmdCtx) -> CtxRes {
+    let res = match ctx.stdin {
+        Some(stdin) => String::from(stdin),
+        None => ctx.args.join(" "),
+    };
+    println!("{}", res);
+    Ok(Some(res))
 }
 
 /// Example function: uptime
@@ -218,3 +273,260 @@ fn sleep(args: CmdArgs) -> CmdRes {
     time::sleep(parse!(args[0], usize)?);
     Ok(None)
 }
+
+/// Example function: vgamode
+///
+/// This function switches the VGA between text mode, the graphics modes 13h and 12h and a Bochs VBE
+/// linear framebuffer (1024x768 unless a resolution is given).
+///
+/// # Arguments
+///
+/// - `args`: The mode to switch to, one of `text`, `13h`, `12h` or `vbe [width] [height]`. Without arguments
+///   the current mode is returned.
+///
+/// # Return
+///
+/// Returns `Ok(None)` if successful, or `Ok(Some(String))` containing the current mode.
+fn vgamode(args: CmdArgs) -> CmdRes {
+    let mode = match args {
+        [] => {
+            return Ok(Some(match vbe::resolution() {
+                Some((w, h)) => format!("Vbe {}x{}", w, h),
+                None => format!("{:?}", mode()),
+            }))
+        }
+        ["text"] => Mode::Text,
+        ["13h"] => Mode::Mode13h,
+        ["12h"] => Mode::Mode12h,
+        ["vbe"] => {
+            return vbe::set_mode(VBE_RES.0, VBE_RES.1)
+                .map(|_| None)
+                .map_err(String::from)
+        }
+        ["vbe", w, h] => {
+            let (w, h) = (parse!(w, u16)?, parse!(h, u16)?);
+            return vbe::set_mode(w, h).map(|_| None).map_err(String::from);
+        }
+        _ => return Err("Usage: vgamode <text|13h|12h|vbe [width] [height]>".to_string()),
+    };
+    set_mode(mode);
+    Ok(None)
+}
+
+/// Example function: draw
+///
+/// This function draws a line or a rectangle in a graphics mode.
+///
+/// # Arguments
+///
+/// - `args`: The shape (`line`, `rect` or `fill`) followed by its coordinates and the colour, either
+///   `x0 y0 x1 y1 colour` for lines or `x y w h colour` for rectangles.
+///
+/// # Return
+///
+/// Returns `Ok(None)` if successful.
+fn draw(args: CmdArgs) -> CmdRes {
+    if args.len() != 6 {
+        return Err(
+            "Usage: draw <line|rect|fill> <usize> <usize> <usize> <usize> <u8>".to_string(),
+        );
+    }
+    if mode() == Mode::Text && !vbe::enabled() {
+        return Err("Not in a graphics mode, use vgamode".to_string());
+    }
+    let n = args[1..5]
+        .iter()
+        .map(|arg| parse!(arg, usize))
+        .collect::<Result<Vec<usize>, String>>()?;
+    let c = parse!(args[5], u8)? as u32;
+    // Lines have two points, rectangles only one with a size which is clipped
+    let mut size = (0, 0);
+    with_surface(|s| size = (s.width(), s.height()));
+    let points = match args[0] {
+        "line" => &n[..4],
+        _ => &n[..2],
+    };
+    if points.chunks(2).any(|p| p[0] >= size.0 || p[1] >= size.1) {
+        return Err(format!(
+            "Usage: draw <line|rect|fill> ... with coordinates within {}x{}",
+            size.0, size.1
+        ));
+    }
+    match args[0] {
+        "line" => with_surface(|s| s.line((n[0], n[1]), (n[2], n[3]), c)),
+        "rect" => with_surface(|s| s.rect(n[0], n[1], n[2], n[3], c)),
+        "fill" => with_surface(|s| s.fill_rect(n[0], n[1], n[2], n[3], c)),
+        _ => return Err("Invalid shape".to_string()),
+    }
+    Ok(None)
+}
+
+/// Example function: dmesg
+///
+/// This function returns the kernel log ring buffer, one record per line with its uptime in ticks, level
+/// and module.
+///
+/// # Arguments
+///
+/// - `args` (optional): `-c` to clear the buffer after reading it, or `-n <level>` to set the lowest level
+///   printed to the screen.
+///
+/// # Return
+///
+/// Returns `Ok(Some(String))` containing the kernel log, or `Ok(None)` if a level was set.
+fn dmesg(args: CmdArgs) -> CmdRes {
+    let res = log::dmesg();
+    match args {
+        [] => {}
+        ["-c"] => log::clear(),
+        ["-n", level] => {
+            let level = level
+                .parse::<Level>()
+                .map_err(|_| format!("Invalid level {}", level))?;
+            log::set_level("vga", level);
+            return Ok(None);
+        }
+        _ => return Err("Usage: dmesg [-c] [-n <error|warn|info|debug|trace>]".to_string()),
+    }
+    Ok(Some(res.trim_end().to_string()))
+}
+
+/// Example function: layout
+///
+/// This function switches the keyboard layout.
+///
+/// # Arguments
+///
+/// - `args`: The layout to switch to, one of `us`, `uk`, `de`, `azerty`, `jis`, `colemak`, `dvorak`,
+///   `dvp` or a compiled in keymap like `ch`. `-f <file>` loads a keymap description from a QEMU firmware
+///   configuration file. Without arguments the current layout is returned.
+///
+/// # Return
+///
+/// Returns `Ok(None)` if successful, or `Ok(Some(String))` containing the current layout.
+fn layout(args: CmdArgs) -> CmdRes {
+    match args {
+        [] => Ok(Some(keys::layout())),
+        ["-f", file] => {
+            keys::set_keymap(keymap::load(file)?);
+            Ok(None)
+        }
+        [name] if keys::select_layout(name) => Ok(None),
+        [_] => {
+            let names = keys::layout_names().collect::<Vec<_>>();
+            Err(format!("Usage: layout [{}|-f <file>]", names.join("|")))
+        }
+        _ => Err("Usage: layout [<name>|-f <file>]".to_string()),
+    }
+}
+
+/// Example function: compose
+///
+/// This function lists or adds the compose sequences used by dead keys and the compose key.
+///
+/// # Arguments
+///
+/// - `args`: The two characters of a sequence and the character they compose to, e.g. `compose <3 ♥`.
+///   Without arguments all sequences are listed.
+///
+/// # Return
+///
+/// Returns `Ok(None)` if successful, or `Ok(Some(String))` containing the sequences.
+fn compose(args: CmdArgs) -> CmdRes {
+    match args {
+        [] => Ok(Some(
+            compose::sequences()
+                .iter()
+                .map(|(first, second, res)| format!("{}{} {}", first, second, res))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        )),
+        [seq, res] => {
+            let (mut seq, mut res) = (seq.chars(), res.chars());
+            match (seq.next(), seq.next(), seq.next(), res.next(), res.next()) {
+                (Some(first), Some(second), None, Some(res), None) => {
+                    compose::add(first, second, res);
+                    Ok(None)
+                }
+                _ => Err("Usage: compose [<two characters> <character>]".to_string()),
+            }
+        }
+        _ => Err("Usage: compose [<two characters> <character>]".to_string()),
+    }
+}
+
+/// Example function: kbdrate
+///
+/// This function changes the key repeat delay and rate.
+///
+/// # Arguments
+///
+/// - `args`: `-d <ms>` sets the delay before a held key repeats (250 to 1000), `-r <hz>` the repeats per
+///   second (2 to 30), `-s` generates the repeats in software and `-k` lets the keyboard generate them.
+///   Without arguments the current settings are returned.
+///
+/// # Return
+///
+/// Returns `Ok(None)` if successful, or `Ok(Some(String))` containing the current settings.
+fn kbdrate(args: CmdArgs) -> CmdRes {
+    const USAGE: &str = "Usage: kbdrate [-d <ms>] [-r <hz>] [-s|-k]";
+    let mut settings = keys::typematic();
+    if args.is_empty() {
+        let source = if settings.software {
+            "software"
+        } else {
+            "keyboard"
+        };
+        return Ok(Some(format!(
+            "delay {} ms, rate {} Hz, repeated by the {}",
+            settings.delay, settings.rate, source
+        )));
+    }
+    let mut args = args.iter();
+    while let Some(&arg) = args.next() {
+        match arg {
+            "-d" | "-r" => {
+                let value = args.next().ok_or(USAGE)?;
+                let value = parse!(value, usize)?;
+                match arg {
+                    "-d" => settings.delay = value,
+                    _ => settings.rate = value,
+                }
+            }
+            "-s" => settings.software = true,
+            "-k" => settings.software = false,
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    keys::set_typematic(settings)?;
+    Ok(None)
+}
+
+/// Example function: history
+///
+/// This function lists the command lines in the history with their numbers, which `!n` refers to.
+///
+/// # Arguments
+///
+/// - `args` (optional): `-c` to clear the history.
+///
+/// # Return
+///
+/// Returns `Ok(Some(String))` containing the history, or `Ok(None)` if it was cleared.
+fn history(args: CmdArgs) -> CmdRes {
+    match args {
+        [] => Ok(Some(
+            HISTORY
+                .read()
+                .numbered()
+                .map(|(n, line)| format!("{:>4}  {}", n, line))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        )),
+        ["-c"] => {
+            HISTORY.write().clear();
+            Ok(None)
+        }
+        _ => Err("Usage: history [-c]".to_string()),
+    }
+}
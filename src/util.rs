@@ -78,13 +78,19 @@ pub fn init(boot_info: &'static BootInfo) {
     kprintln!("Beginning initialization");
     kprintln!("Setting temporary time handler fn(s)");
     set_irq_handler(0, none);
+    kinit!(io::vga);
     kinit!(interrupt::gdt);
     kinit!(interrupt::idt);
     kinit!(interrupt::pic);
     kinit!(time);
-    kinit!(keys);
     let (mut mapper, mut frame_alloc) = kinit!(mem::paging, boot_info);
     kinit!(mem::alloc::init, &mut mapper, &mut frame_alloc).expect("Heap init failed");
+    crate::mem::paging::store(mapper, frame_alloc);
+    kinit!(io::vt);
+    kinit!(io::ps2);
+    kinit!(keys);
+    kinit!(io::mouse);
+    kinit!(io::gfx);
     kinit!(console);
     kprintln!("Kernel fully initialized");
 }